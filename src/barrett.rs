@@ -0,0 +1,220 @@
+use num::{Zero, One, Integer, BigUint};
+use modexp::{Exponent, sliding_window_exp};
+use modinv::ModInv;
+use error::ModError;
+
+
+// Number of bits in a limb of a BigUint.
+const LIMB_BITS: usize = 32;
+
+/// A data structure storing the precomputed constants for Barrett reduction
+/// modulo an arbitrary nonzero modulus m.
+///
+/// Given a modulus m occupying k limbs in base b, the context stores
+/// ```text
+/// mu == floor(b ^ 2k / m)
+/// ```
+/// so that any ```0 <= x < b ^ 2k``` can be reduced modulo m with two
+/// multiplications and a few subtractions instead of a division. Unlike
+/// Montgomery multiplication, Barrett reduction does not require m to be odd.
+///
+/// See Algorithm 14.42 of the 'Handbook of Applied Cryptography'.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigUint;
+/// use modal::BarrettContext;
+///
+/// fn main() {
+///     let ctx = BarrettContext::new(&BigUint::from(800u32)).unwrap();
+///     let x   = BigUint::from(596u32);
+///
+///     assert_eq!(ctx.mod_mult(&x, &x), BigUint::from(16u32));
+///     assert_eq!(ctx.mod_exp(&x, &BigUint::from(240u32)), BigUint::from(576u32));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct BarrettContext {
+    modulus: BigUint,
+    mu:      BigUint,
+    limbs:   usize,
+}
+
+impl BarrettContext {
+    /// Constructs a Barrett context for the modulus m.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the modulus is zero.
+    pub fn new(modulus: &BigUint) -> Option<BarrettContext> {
+        if modulus.is_zero() {
+            return None;
+        }
+
+        let limbs = modulus.bits().div_ceil(LIMB_BITS);
+        let b2k: BigUint = <BigUint as One>::one() << (2 * limbs * LIMB_BITS);
+
+        Some(BarrettContext {
+            modulus: modulus.clone(),
+            mu: b2k / modulus,
+            limbs,
+        })
+    }
+
+    /// Returns the modulus of the context.
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    /// Computes ```x (mod m)```. Values below ```b ^ 2k```, which includes every
+    /// product of two residues, are reduced without dividing by m; larger values
+    /// fall back to a full division.
+    pub fn reduce(&self, x: &BigUint) -> BigUint {
+        if x < &self.modulus {
+            return x.clone();
+        }
+
+        if x.bits() > 2 * self.limbs * LIMB_BITS {
+            return x.mod_floor(&self.modulus);
+        }
+
+        let q1 = x >> ((self.limbs - 1) * LIMB_BITS);
+        let q3 = (q1 * &self.mu) >> ((self.limbs + 1) * LIMB_BITS);
+        // q3 never exceeds floor(x / m), and falls short of it by at most two.
+        let mut r = x - q3 * &self.modulus;
+
+        while r >= self.modulus {
+            r -= &self.modulus;
+        }
+
+        r
+    }
+
+    /// Computes ```x * y (mod m)```.
+    pub fn mod_mult(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        self.reduce(&(self.reduce(x) * self.reduce(y)))
+    }
+
+    /// Computes ```b ^ e (mod m)``` where b is the base and e is the exponent,
+    /// by left to right sliding window exponentiation. A negative exponent
+    /// raises the inverse of b to ```|e|```.
+    ///
+    /// # Panics
+    /// when the exponent is negative and b has no inverse modulo m.
+    pub fn mod_exp<E: Exponent + ?Sized>(&self, base: &BigUint, exponent: &E) -> BigUint {
+        self.checked_mod_exp(base, exponent).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Computes ```b ^ e (mod m)``` like `mod_exp`, returning an error instead
+    /// of panicking.
+    ///
+    /// # Errors
+    /// `ModError::NotInvertible` with the common factor of b and m when the
+    /// exponent is negative and b has no inverse.
+    pub fn checked_mod_exp<E: Exponent + ?Sized>(&self, base: &BigUint, exponent: &E) -> Result<BigUint, ModError<BigUint>> {
+        if exponent.is_negative() {
+            let base_inv = base.try_mod_inv(&self.modulus)?;
+
+            Ok(self.mod_exp_abs(&base_inv, exponent))
+        } else {
+            Ok(self.mod_exp_abs(base, exponent))
+        }
+    }
+
+    // Computes b ^ |e| (mod m), ignoring the sign of the exponent.
+    pub(crate) fn mod_exp_abs<E: Exponent + ?Sized>(&self, base: &BigUint, exponent: &E) -> BigUint {
+        sliding_window_exp(self.reduce(&One::one()), self.reduce(base), exponent, |x, y| self.reduce(&(x * y)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num::{BigUint, Num, Integer};
+    use error::ModError;
+    use super::BarrettContext;
+
+
+    struct TestCase {
+        x:       BigUint,
+        y:       BigUint,
+        modulus: BigUint,
+    }
+
+    struct Test {
+        data: Vec<TestCase>,
+    }
+
+    fn barrett_test_cases() -> Test {
+        Test {
+            data: vec![
+                TestCase {
+                    x:       BigUint::from(464283712u32),
+                    y:       BigUint::from(559073817u32),
+                    modulus: BigUint::from(635717262u32),
+                },
+                TestCase {
+                    x:       BigUint::from(735172765u32),
+                    y:       BigUint::from(446253906u32),
+                    modulus: BigUint::from(53235608u32),
+                },
+                TestCase {
+                    x:       BigUint::from(7u32),
+                    y:       BigUint::from(9u32),
+                    modulus: BigUint::from(1u32),
+                },
+                TestCase {
+                    // 2^130 * an odd factor.
+                    x:       <BigUint as Num>::from_str_radix("29424885190633566625547596530635633692994051181214434796327203075", 10).unwrap(),
+                    y:       <BigUint as Num>::from_str_radix("919087970205406919189208074679995123273961", 10).unwrap(),
+                    modulus: <BigUint as Num>::from_str_radix("bd5c0a1f6e4d3b8a400000000000000000000000000000000", 16).unwrap(),
+                },
+                TestCase {
+                    x:       <BigUint as Num>::from_str_radix("ffffffffffffffffffffffffffffffffffffffffffffffff", 16).unwrap(),
+                    y:       <BigUint as Num>::from_str_radix("fffffffffffffffffffffffffffffffffffffffffffffffe", 16).unwrap(),
+                    modulus: <BigUint as Num>::from_str_radix("100000000000000000000000000000000", 16).unwrap(),
+                }
+            ]
+        }
+    }
+
+    fn run_tests(test: &Test) {
+        for test_case in test.data.iter() {
+            let ctx = BarrettContext::new(&test_case.modulus).unwrap();
+            let expected = (&test_case.x * &test_case.y).mod_floor(&test_case.modulus);
+
+            assert_eq!(ctx.mod_mult(&test_case.x, &test_case.y), expected);
+            assert_eq!(ctx.reduce(&test_case.x), test_case.x.mod_floor(&test_case.modulus));
+        }
+    }
+
+    #[test]
+    fn test_barrett_mult() {
+        run_tests(&barrett_test_cases());
+    }
+
+    #[test]
+    fn test_barrett_exp() {
+        let ctx = BarrettContext::new(&BigUint::from(610u32)).unwrap();
+
+        assert_eq!(ctx.mod_exp(&BigUint::from(131u32), &BigUint::from(870u32)), BigUint::from(1u32));
+        assert_eq!(ctx.mod_exp(&BigUint::from(131u32), &BigUint::from(0u32)), BigUint::from(1u32));
+    }
+
+    #[test]
+    fn test_barrett_exp_negative_exponent() {
+        let ctx = BarrettContext::new(&BigUint::from(610u32)).unwrap();
+        let x = BigUint::from(131u32);
+
+        assert_eq!(ctx.mod_mult(&ctx.mod_exp(&x, &-5i32), &ctx.mod_exp(&x, &5i32)), BigUint::from(1u32));
+        assert_eq!(ctx.checked_mod_exp(&BigUint::from(6u32), &-5i32), Err(ModError::NotInvertible { gcd: BigUint::from(2u32) }));
+    }
+
+    #[test]
+    fn test_barrett_zero_modulus() {
+        assert!(BarrettContext::new(&BigUint::from(0u32)).is_none());
+    }
+}
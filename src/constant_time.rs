@@ -0,0 +1,452 @@
+use num::{Zero, One, Integer, BigUint};
+use montgomery::neg_inv_u64;
+use std::hint;
+use std::ptr;
+
+
+// Number of bits in a limb of a constant time residue.
+const LIMB_BITS: usize = 64;
+
+// Number of exponent bits consumed per step of the fixed window exponentiation.
+const WINDOW_BITS: usize = 4;
+
+// Returns a mask of all ones when bit is one, and zero when bit is zero.
+// The mask is passed through black_box so that the optimizer cannot turn the
+// selections built on it back into branches.
+#[inline]
+fn __mask(bit: u64) -> u64 {
+    hint::black_box(0u64.wrapping_sub(bit))
+}
+
+// Returns a mask of all ones when x == y, and zero otherwise.
+#[inline]
+fn __eq_mask(x: u64, y: u64) -> u64 {
+    let diff = x ^ y;
+
+    __mask(1 ^ ((diff | diff.wrapping_neg()) >> 63))
+}
+
+// Selects x where the mask is all ones, and y where it is zero.
+#[inline]
+fn __select<const N: usize>(mask: u64, x: &[u64; N], y: &[u64; N]) -> [u64; N] {
+    let mut result = [0u64; N];
+    for i in 0..N {
+        result[i] = (x[i] & mask) | (y[i] & !mask);
+    }
+
+    result
+}
+
+// Computes x + y, returning the sum and the carry out of the top limb.
+#[inline]
+fn __add<const N: usize>(x: &[u64; N], y: &[u64; N]) -> ([u64; N], u64) {
+    let mut sum = [0u64; N];
+    let mut carry = 0u64;
+    for i in 0..N {
+        let s = (x[i] as u128) + (y[i] as u128) + (carry as u128);
+        sum[i] = s as u64;
+        carry = (s >> LIMB_BITS) as u64;
+    }
+
+    (sum, carry)
+}
+
+// Computes x - y, returning the difference and the borrow out of the top limb.
+#[inline]
+fn __sub<const N: usize>(x: &[u64; N], y: &[u64; N]) -> ([u64; N], u64) {
+    let mut diff = [0u64; N];
+    let mut borrow = 0u64;
+    for i in 0..N {
+        let d = (x[i] as u128).wrapping_sub(y[i] as u128).wrapping_sub(borrow as u128);
+        diff[i] = d as u64;
+        borrow = ((d >> LIMB_BITS) as u64) & 1;
+    }
+
+    (diff, borrow)
+}
+
+/// A data structure storing the precomputed constants for constant time
+/// arithmetic modulo an odd modulus m of at most N 64 bit limbs.
+///
+/// Residues are `ConstTimeResidue` values created by `residue`, which keep
+/// their value in Montgomery form with the radix
+/// ```text
+/// R == 2 ^ (64 * N)
+/// ```
+/// so that a product costs a single Montgomery reduction. The running time
+/// and memory access pattern of `mod_add`, `mod_sub`, `mod_mult`, and
+/// `mod_exp` depend only on N and on the number of exponent limbs, never on
+/// the values of the residues or the bits of the exponent, so they are
+/// suitable for secret values such as private keys. Exponentiation uses
+/// fixed windows of four bits with a table lookup that reads every entry.
+///
+/// Constructing a context and converting to and from `BigUint` are not
+/// constant time; the modulus is assumed to be public.
+///
+/// See Algorithm 14.36 of the 'Handbook of Applied Cryptography'.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigUint;
+/// use modal::ConstTimeContext;
+///
+/// fn main() {
+///     let ctx = ConstTimeContext::<1>::new(&BigUint::from(833u32)).unwrap();
+///     let x   = ctx.residue(&BigUint::from(365u32)).unwrap();
+///
+///     assert_eq!(ctx.to_biguint(&ctx.mod_exp(&x, &[915])), BigUint::from(155u32));
+///     assert_eq!(ctx.to_biguint(&ctx.mod_mult(&x, &x)), BigUint::from(778u32));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConstTimeContext<const N: usize> {
+    modulus: [u64; N],
+    r_mod:   [u64; N],
+    r2_mod:  [u64; N],
+    m_prime: u64,
+}
+
+/// A residue modulo the modulus of a `ConstTimeContext`, stored as N limbs in
+/// Montgomery form and tied to the context that created it.
+///
+/// Residues are only created by `ConstTimeContext::residue`, so their value
+/// always lies in ```[0, m)```, and are converted back with
+/// `ConstTimeContext::to_biguint`.
+#[derive(Clone, Copy, Debug)]
+pub struct ConstTimeResidue<'a, const N: usize> {
+    value: [u64; N],
+    ctx:   &'a ConstTimeContext<N>,
+}
+
+// Splits a value below 2^(64 * N) into N limbs.
+fn __to_limbs<const N: usize>(x: &BigUint) -> [u64; N] {
+    let mut limbs = [0u64; N];
+    for (i, chunk) in x.to_bytes_le().chunks(8).enumerate() {
+        for (j, &byte) in chunk.iter().enumerate() {
+            limbs[i] |= (byte as u64) << (8 * j);
+        }
+    }
+
+    limbs
+}
+
+// Joins N limbs, least significant limb first, into a value.
+fn __from_limbs<const N: usize>(x: &[u64; N]) -> BigUint {
+    x.iter().rev().fold(BigUint::zero(), |acc, &limb| (acc << LIMB_BITS) + BigUint::from(limb))
+}
+
+impl<const N: usize> ConstTimeContext<N> {
+    /// Constructs a constant time context for the modulus m.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the modulus is even or zero, or does not fit
+    /// in N limbs.
+    pub fn new(modulus: &BigUint) -> Option<ConstTimeContext<N>> {
+        if modulus.is_even() || modulus.bits() > N * LIMB_BITS {
+            return None;
+        }
+
+        let r: BigUint = <BigUint as One>::one() << (N * LIMB_BITS);
+        let r_mod = r.mod_floor(modulus);
+        let r2_mod = (&r_mod * &r_mod).mod_floor(modulus);
+        let limbs = __to_limbs(modulus);
+
+        Some(ConstTimeContext {
+            modulus: limbs,
+            r_mod: __to_limbs(&r_mod),
+            r2_mod: __to_limbs(&r2_mod),
+            m_prime: neg_inv_u64(limbs[0]),
+        })
+    }
+
+    /// Returns the modulus of the context.
+    pub fn modulus(&self) -> BigUint {
+        __from_limbs(&self.modulus)
+    }
+
+    /// Converts a value ```0 <= x < m``` into a residue in Montgomery form.
+    ///
+    /// # Errors
+    /// Returns ```None``` when x is not below the modulus.
+    pub fn residue(&self, x: &BigUint) -> Option<ConstTimeResidue<'_, N>> {
+        if *x < self.modulus() {
+            Some(self.wrap(self.montgomery_mult(&__to_limbs(x), &self.r2_mod)))
+        } else {
+            None
+        }
+    }
+
+    /// Converts a residue out of Montgomery form into a `BigUint`.
+    ///
+    /// # Panics
+    /// when the residue belongs to a different context.
+    pub fn to_biguint(&self, x: &ConstTimeResidue<N>) -> BigUint {
+        self.check_context(x);
+
+        let mut one = [0u64; N];
+        one[0] = 1;
+
+        __from_limbs(&self.montgomery_mult(&x.value, &one))
+    }
+
+    /// Computes ```x + y (mod m)``` in constant time.
+    ///
+    /// # Panics
+    /// when a residue belongs to a different context.
+    pub fn mod_add<'a>(&'a self, x: &ConstTimeResidue<'a, N>, y: &ConstTimeResidue<'a, N>) -> ConstTimeResidue<'a, N> {
+        self.check_context(x);
+        self.check_context(y);
+
+        let (sum, carry) = __add(&x.value, &y.value);
+
+        self.wrap(self.reduce_once(&sum, carry))
+    }
+
+    /// Computes ```x - y (mod m)``` in constant time.
+    ///
+    /// # Panics
+    /// when a residue belongs to a different context.
+    pub fn mod_sub<'a>(&'a self, x: &ConstTimeResidue<'a, N>, y: &ConstTimeResidue<'a, N>) -> ConstTimeResidue<'a, N> {
+        self.check_context(x);
+        self.check_context(y);
+
+        let (diff, borrow) = __sub(&x.value, &y.value);
+        let (wrapped, _) = __add(&diff, &self.modulus);
+
+        self.wrap(__select(__mask(borrow), &wrapped, &diff))
+    }
+
+    /// Computes ```x * y (mod m)``` in constant time, with a single
+    /// Montgomery reduction.
+    ///
+    /// # Panics
+    /// when a residue belongs to a different context.
+    pub fn mod_mult<'a>(&'a self, x: &ConstTimeResidue<'a, N>, y: &ConstTimeResidue<'a, N>) -> ConstTimeResidue<'a, N> {
+        self.check_context(x);
+        self.check_context(y);
+
+        self.wrap(self.montgomery_mult(&x.value, &y.value))
+    }
+
+    /// Computes ```b ^ e (mod m)``` in constant time, where the exponent e is
+    /// given as limbs, least significant limb first. Only the number of
+    /// exponent limbs affects the running time, so leading zero limbs can be
+    /// used to hide the length of the exponent.
+    ///
+    /// # Panics
+    /// when the base belongs to a different context.
+    pub fn mod_exp<'a>(&'a self, base: &ConstTimeResidue<'a, N>, exponent: &[u64]) -> ConstTimeResidue<'a, N> {
+        self.check_context(base);
+
+        let mut table = [[0u64; N]; 1 << WINDOW_BITS];
+        table[0] = self.r_mod;
+        table[1] = base.value;
+        for i in 2..table.len() {
+            table[i] = self.montgomery_mult(&table[i - 1], &table[1]);
+        }
+
+        let mut result = self.r_mod;
+        for &limb in exponent.iter().rev() {
+            for window in (0..(LIMB_BITS / WINDOW_BITS)).rev() {
+                for _ in 0..WINDOW_BITS {
+                    result = self.montgomery_mult(&result, &result);
+                }
+
+                let index = (limb >> (window * WINDOW_BITS)) & ((1 << WINDOW_BITS) - 1);
+                // Every multiplication happens, including by the table entry
+                // for one when the window is zero.
+                result = self.montgomery_mult(&result, &Self::lookup(&table, index));
+            }
+        }
+
+        self.wrap(result)
+    }
+
+    // Wraps a value in Montgomery form as a residue of this context.
+    #[inline]
+    fn wrap(&self, value: [u64; N]) -> ConstTimeResidue<'_, N> {
+        ConstTimeResidue { value, ctx: self }
+    }
+
+    // Panics unless the residue was created by this context.
+    #[inline]
+    fn check_context(&self, x: &ConstTimeResidue<N>) {
+        assert!(ptr::eq(self, x.ctx), "constant time residue belongs to a different context");
+    }
+
+    // Reads table[index] by touching every entry, so that the memory access
+    // pattern does not depend on the index.
+    #[inline]
+    fn lookup(table: &[[u64; N]], index: u64) -> [u64; N] {
+        let mut entry = [0u64; N];
+        for (i, candidate) in table.iter().enumerate() {
+            let mask = __eq_mask(i as u64, index);
+            for j in 0..N {
+                entry[j] |= candidate[j] & mask;
+            }
+        }
+
+        entry
+    }
+
+    // Subtracts the modulus from a value ```x + carry * R < 2m``` when the
+    // value is at least m.
+    #[inline]
+    fn reduce_once(&self, x: &[u64; N], carry: u64) -> [u64; N] {
+        let (diff, borrow) = __sub(x, &self.modulus);
+
+        // The value is below m exactly when the subtraction borrows and the
+        // carry is clear.
+        __select(__mask(borrow & (carry ^ 1)), x, &diff)
+    }
+
+    // Computes the Montgomery product x * y * R^-1 (mod m) by coarsely
+    // integrated operand scanning, interleaving the product and reduction one
+    // limb of x at a time.
+    fn montgomery_mult(&self, x: &[u64; N], y: &[u64; N]) -> [u64; N] {
+        let mut t = [0u64; N];
+        let mut t_high = 0u64;
+
+        for &x_i in x.iter() {
+            let mut carry = 0u64;
+            for j in 0..N {
+                let s = (t[j] as u128) + (x_i as u128) * (y[j] as u128) + (carry as u128);
+                t[j] = s as u64;
+                carry = (s >> LIMB_BITS) as u64;
+            }
+            let s = (t_high as u128) + (carry as u128);
+            t_high = s as u64;
+            let t_top = (s >> LIMB_BITS) as u64;
+
+            // Adding u * m clears the low limb, which is shifted out.
+            let u = t[0].wrapping_mul(self.m_prime);
+            let s = (t[0] as u128) + (u as u128) * (self.modulus[0] as u128);
+            let mut carry = (s >> LIMB_BITS) as u64;
+            for j in 1..N {
+                let s = (t[j] as u128) + (u as u128) * (self.modulus[j] as u128) + (carry as u128);
+                t[j - 1] = s as u64;
+                carry = (s >> LIMB_BITS) as u64;
+            }
+            let s = (t_high as u128) + (carry as u128);
+            t[N - 1] = s as u64;
+            t_high = t_top + ((s >> LIMB_BITS) as u64);
+        }
+
+        self.reduce_once(&t, t_high)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num::{BigUint, Num, Integer};
+    use modexp::ModExp;
+    use super::ConstTimeContext;
+
+
+    fn p256() -> BigUint {
+        <BigUint as Num>::from_str_radix("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff", 16).unwrap()
+    }
+
+    fn values() -> Vec<BigUint> {
+        vec![
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(2u32),
+            <BigUint as Num>::from_str_radix("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296", 16).unwrap(),
+            <BigUint as Num>::from_str_radix("4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5", 16).unwrap(),
+            p256() - BigUint::from(1u32),
+        ]
+    }
+
+    #[test]
+    fn test_constant_time_arithmetic() {
+        let modulus = p256();
+        let ctx = ConstTimeContext::<4>::new(&modulus).unwrap();
+
+        for x in values().iter() {
+            for y in values().iter() {
+                let x_res = ctx.residue(x).unwrap();
+                let y_res = ctx.residue(y).unwrap();
+
+                assert_eq!(ctx.to_biguint(&x_res), *x);
+                assert_eq!(ctx.to_biguint(&ctx.mod_add(&x_res, &y_res)), (x + y).mod_floor(&modulus));
+                assert_eq!(ctx.to_biguint(&ctx.mod_sub(&x_res, &y_res)), (x + &modulus - y).mod_floor(&modulus));
+                assert_eq!(ctx.to_biguint(&ctx.mod_mult(&x_res, &y_res)), (x * y).mod_floor(&modulus));
+            }
+        }
+    }
+
+    #[test]
+    fn test_constant_time_mod_exp() {
+        let modulus = p256();
+        let ctx = ConstTimeContext::<4>::new(&modulus).unwrap();
+        let exponents: [[u64; 2]; 4] = [[0, 0], [1, 0], [0xfedcba9876543210, 0x0123456789abcdef], [u64::MAX, u64::MAX]];
+
+        for x in values().iter() {
+            let x_res = ctx.residue(x).unwrap();
+
+            for exponent in exponents.iter() {
+                let e = (BigUint::from(exponent[1]) << 64) + BigUint::from(exponent[0]);
+
+                assert_eq!(ctx.to_biguint(&ctx.mod_exp(&x_res, exponent)), x.mod_exp(&e, &modulus));
+            }
+        }
+    }
+
+    #[test]
+    fn test_constant_time_small_moduli() {
+        // A modulus much smaller than the radix, and one just below it.
+        for &modulus in [1u64, 833, 0xffffffffffffffc5].iter() {
+            let ctx = ConstTimeContext::<2>::new(&BigUint::from(modulus)).unwrap();
+            let x = ctx.residue(&BigUint::from(modulus - 1)).unwrap();
+            let expected = (modulus - 1).mod_exp(&0x123456789abcdefu64, &modulus);
+
+            assert_eq!(ctx.to_biguint(&ctx.mod_exp(&x, &[0x123456789abcdef])), BigUint::from(expected));
+            assert_eq!(ctx.modulus(), BigUint::from(modulus));
+        }
+    }
+
+    #[test]
+    fn test_constant_time_invalid() {
+        assert!(ConstTimeContext::<1>::new(&BigUint::from(0u32)).is_none());
+        assert!(ConstTimeContext::<1>::new(&BigUint::from(800u32)).is_none());
+        assert!(ConstTimeContext::<3>::new(&p256()).is_none());
+
+        let ctx = ConstTimeContext::<1>::new(&BigUint::from(833u32)).unwrap();
+
+        assert!(ctx.residue(&BigUint::from(833u32)).is_none());
+    }
+
+    #[test]
+    fn test_constant_time_chained_products() {
+        let modulus = p256();
+        let ctx = ConstTimeContext::<4>::new(&modulus).unwrap();
+        let values = values();
+        let x = ctx.residue(&values[3]).unwrap();
+        let mut acc = ctx.residue(&values[1]).unwrap();
+        let mut expected = BigUint::from(1u32);
+
+        for _ in 0..50 {
+            acc = ctx.mod_add(&ctx.mod_mult(&acc, &x), &x);
+            expected = (&expected * &values[3] + &values[3]).mod_floor(&modulus);
+        }
+
+        assert_eq!(ctx.to_biguint(&acc), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_constant_time_context_mismatch() {
+        let ctx1 = ConstTimeContext::<1>::new(&BigUint::from(833u32)).unwrap();
+        let ctx2 = ConstTimeContext::<1>::new(&BigUint::from(833u32)).unwrap();
+        let x = ctx1.residue(&BigUint::from(3u32)).unwrap();
+        let y = ctx2.residue(&BigUint::from(3u32)).unwrap();
+
+        ctx1.mod_mult(&x, &y);
+    }
+}
@@ -0,0 +1,105 @@
+use num::Zero;
+use std::error::Error;
+use std::fmt;
+
+
+/// The `ModError` type enumerates the ways a modular arithmetic operation
+/// can fail, for the non-panicking variants of the operations in this crate.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ModError<T> {
+    /// The modulus is zero.
+    ZeroModulus,
+    /// The operands of a binary operation are residues modulo different moduli.
+    ModulusMismatch {
+        left:  T,
+        right: T,
+    },
+    /// The value has no inverse modulo the modulus, since the two share the
+    /// nontrivial common factor `gcd`.
+    NotInvertible {
+        gcd: T,
+    },
+    /// An input that must be nonnegative is negative.
+    NegativeInput,
+    /// A value is not representable in the integer type, such as the absolute
+    /// value of the minimum of a signed type.
+    Overflow,
+}
+
+impl<T> fmt::Display for ModError<T> where T: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModError::ZeroModulus => {
+                write!(f, "the modulus is zero")
+            }
+            ModError::ModulusMismatch { ref left, ref right } => {
+                write!(f, "modulus mismatch: {} != {}", left, right)
+            }
+            ModError::NotInvertible { ref gcd } => {
+                write!(f, "value is not invertible: shares the factor {} with the modulus", gcd)
+            }
+            ModError::NegativeInput => {
+                write!(f, "the input is negative")
+            }
+            ModError::Overflow => {
+                write!(f, "the result overflows the integer type")
+            }
+        }
+    }
+}
+
+impl<T> Error for ModError<T> where T: fmt::Debug + fmt::Display {}
+
+// Checks that a modulus is nonzero, for the checked variants of the traits.
+#[inline]
+pub(crate) fn nonzero_modulus<T: Zero>(modulus: &T) -> Result<(), ModError<T>> {
+    if modulus.is_zero() {
+        Err(ModError::ZeroModulus)
+    } else {
+        Ok(())
+    }
+}
+
+// Checks that a modulus is positive, for operations that only work with
+// residues in ```[0, m)```.
+#[inline]
+pub(crate) fn positive_modulus<T: Zero + PartialOrd>(modulus: &T) -> Result<(), ModError<T>> {
+    nonzero_modulus(modulus)?;
+
+    if *modulus < T::zero() {
+        Err(ModError::NegativeInput)
+    } else {
+        Ok(())
+    }
+}
+
+/// The `ParseModError` type enumerates the ways parsing a residue class from
+/// a string of the form ```5 (mod 7)``` can fail. `E` is the error type of
+/// the integer parser.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseModError<E> {
+    /// The string is not of the form ```value (mod modulus)```.
+    InvalidFormat,
+    /// The value or the modulus is not a valid integer.
+    InvalidInteger(E),
+    /// The modulus is zero.
+    ZeroModulus,
+}
+
+impl<E> fmt::Display for ParseModError<E> where E: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseModError::InvalidFormat => {
+                write!(f, "expected a residue of the form `value (mod modulus)`")
+            }
+            ParseModError::InvalidInteger(ref err) => {
+                write!(f, "invalid integer: {}", err)
+            }
+            ParseModError::ZeroModulus => {
+                write!(f, "the modulus is zero")
+            }
+        }
+    }
+}
+
+impl<E> Error for ParseModError<E> where E: fmt::Debug + fmt::Display {}
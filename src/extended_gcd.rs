@@ -1,9 +1,18 @@
-use num::{Integer, Zero, One, BigInt, PrimInt};
-use num::bigint::Sign;
+use num::{Integer, Zero, One, Signed, Bounded, BigInt, PrimInt};
+use error::ModError;
 
 
 /// A data structure storing the results of computing the greatest common
 /// divisor of two integers.
+///
+/// The gcd `gcd_xy` is always nonnegative, and the coefficients satisfy
+/// ```text
+/// coef_x * x + coef_y * y == gcd_xy
+/// ```
+/// with the signs of x and y already folded into `coef_x` and `coef_y`. The
+/// field `g` holds the power of two the binary algorithm factored out of x
+/// and y for `BigInt`, and is one for the primitive types, which use the
+/// Euclidean algorithm, and when either input is zero.
 #[derive(Clone)]
 pub struct Gcd<T> where T: Clone {
     pub coef_x: T,
@@ -12,11 +21,139 @@ pub struct Gcd<T> where T: Clone {
     pub gcd_xy: T,
 }
 
+impl<T> Gcd<T> where T: Integer + Signed + Clone {
+    /// Returns the canonical minimal solution of
+    /// ```text
+    /// a * x + b * y == gcd(x, y)
+    /// ```
+    /// for the inputs x and y that produced this result. Writing d for
+    /// ```gcd(x, y)```, the value of `gcd_xy` (not the field `g`), the
+    /// coefficient a is chosen in the symmetric range
+    /// ```text
+    /// -|y| / 2d < a <= |y| / 2d
+    /// ```
+    /// which makes the solution unique and gives ```|a| <= |y| / 2d``` and, when
+    /// x is nonzero, ```|b| <= |x| / d```. When x is zero, the solution is
+    /// ```(0, sign(y))```.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate num;
+    /// extern crate modal;
+    ///
+    /// use num::BigInt;
+    /// use modal::ExtendedGcd;
+    ///
+    /// fn main() {
+    ///     let x = BigInt::from(693);
+    ///     let y = BigInt::from(609);
+    ///     let gcd = x.extended_gcd(&y).unwrap().minimal(&x, &y);
+    ///
+    ///     assert_eq!(gcd.coef_x, BigInt::from(-7));
+    ///     assert_eq!(gcd.coef_y, BigInt::from(8));
+    /// }
+    /// ```
+    pub fn minimal(&self, x: &T, y: &T) -> Gcd<T> {
+        if self.gcd_xy.is_zero() || y.is_zero() {
+            return self.clone();
+        }
+
+        // Moving k steps along the solution family changes a by k * |y / d|
+        // and b by the matching multiple of x / d, where d is gcd_xy. Every quantity formed here
+        // is bounded by the coefficients or the quotients, so the shift cannot
+        // overflow a primitive type that holds them.
+        let step_x = x.clone() / self.gcd_xy.clone();
+        let step_y = y.clone() / self.gcd_xy.clone();
+        let step = step_y.abs();
+        let (mut k, mut coef_x) = self.coef_x.div_mod_floor(&step);
+
+        if coef_x > step.clone() - coef_x.clone() {
+            coef_x = coef_x - step;
+            k = k + T::one();
+        }
+
+        let coef_y = if step_y.is_negative() {
+            self.coef_y.clone() - k * step_x
+        } else {
+            self.coef_y.clone() + k * step_x
+        };
+
+        Gcd {
+            coef_x,
+            coef_y,
+            g:      self.g.clone(),
+            gcd_xy: self.gcd_xy.clone(),
+        }
+    }
+
+    /// Returns the member of the general solution family
+    /// ```text
+    /// (a + k * y / d, b - k * x / d)
+    /// ```
+    /// for the integer k, where (a, b) are the coefficients of this result and
+    /// d is ```gcd(x, y)```, the value of `gcd_xy`. Every solution of
+    /// ```a * x + b * y == d``` has this form. When x and y are both zero, the
+    /// only solution reported is (0, 0).
+    pub fn solution(&self, x: &T, y: &T, k: &T) -> (T, T) {
+        if self.gcd_xy.is_zero() {
+            return (self.coef_x.clone(), self.coef_y.clone());
+        }
+
+        let step_x = y.clone() / self.gcd_xy.clone();
+        let step_y = x.clone() / self.gcd_xy.clone();
+
+        (self.coef_x.clone() + k.clone() * step_x, self.coef_y.clone() - k.clone() * step_y)
+    }
+
+    /// Enumerates the general solution family, starting from the minimal
+    /// solution and moving outward: k == 0, 1, -1, 2, -2, ...
+    pub fn solutions(&self, x: &T, y: &T) -> BezoutSolutions<T> {
+        BezoutSolutions {
+            base: self.minimal(x, y),
+            x:    x.clone(),
+            y:    y.clone(),
+            k:    T::zero(),
+        }
+    }
+}
+
+/// An iterator over the solutions of a Bezout identity, created by
+/// `Gcd::solutions`.
+pub struct BezoutSolutions<T> where T: Clone {
+    base: Gcd<T>,
+    x:    T,
+    y:    T,
+    k:    T,
+}
+
+impl<T> Iterator for BezoutSolutions<T> where T: Integer + Signed + Clone {
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<(T, T)> {
+        if self.base.gcd_xy.is_zero() && !self.k.is_zero() {
+            return None;
+        }
+
+        let solution = self.base.solution(&self.x, &self.y, &self.k);
+
+        self.k = if self.k.is_positive() {
+            -self.k.clone()
+        } else {
+            T::one() - self.k.clone()
+        };
+
+        Some(solution)
+    }
+}
+
 /// Extended Gcd Algorithm trait.
 pub trait ExtendedGcd<T> where T: Clone {
-    /// Implementation of the binary extended gcd algorithm.
+    /// Implementation of the extended gcd algorithm: the binary algorithm for
+    /// `BigInt`, and the Euclidean algorithm for the primitive types, whose
+    /// coefficients stay within the range of the type for all inputs.
     ///
-    /// See Algorithm 14.61 of the 'Handbook of Applied Cryptography'.
+    /// See Algorithms 14.61 and 2.107 of the 'Handbook of Applied Cryptography'.
     ///
     /// Given integers x and y, compute integers a and b such that
     /// ```text
@@ -25,8 +162,18 @@ pub trait ExtendedGcd<T> where T: Clone {
     /// Note that the coefficients found by the extended GCD algorithm are not unique: That is,
     /// there is more than one set of solutions to the diophantine equation above.
     ///
+    /// Every pair of integers is accepted. The gcd is computed from ```|x|``` and
+    /// ```|y|```, and is always nonnegative; a negative input flips the sign of its
+    /// coefficient. By convention
+    /// ```text
+    /// gcd(x, 0) == |x|   with coef_x == sign(x), coef_y == 0
+    /// gcd(0, y) == |y|   with coef_x == 0,       coef_y == sign(y)
+    /// gcd(0, 0) == 0     with coef_x == 0,       coef_y == 0
+    /// ```
+    ///
     /// # Safety
-    /// Returns ```None``` if ```x < 0``` or ```y < 0```.
+    /// Returns ```None``` for primitive types when x or y is the minimum value of
+    /// the type, since its absolute value is not representable.
     ///
     /// # Examples
     /// 
@@ -45,6 +192,15 @@ pub trait ExtendedGcd<T> where T: Clone {
     /// ```
     fn extended_gcd(&self, y: &T) -> Option<Gcd<T>>;
 
+    /// Computes the extended gcd like `extended_gcd`, reporting why it cannot.
+    ///
+    /// # Errors
+    /// `ModError::Overflow` for primitive types when x or y is the minimum
+    /// value of the type.
+    fn try_extended_gcd(&self, y: &T) -> Result<Gcd<T>, ModError<T>> {
+        self.extended_gcd(y).ok_or(ModError::Overflow)
+    }
+
     /// Tests whether a pair of coefficients coef_x and coef_t are 
     /// valid solutions to the equation
     /// ```text
@@ -75,19 +231,48 @@ pub trait ExtendedGcd<T> where T: Clone {
     fn valid_solution(x: &T, y: &T, coef_x: &T, coef_y: &T, gcd_xy: &T) -> bool;
 }
 
-impl ExtendedGcd<BigInt> for BigInt {
-    fn extended_gcd(&self, y: &BigInt) -> Option<Gcd<BigInt>> {
-        if (self.sign() == Sign::Minus) || (y.sign() == Sign::Minus) {
-            return None;
+// Extends an extended gcd algorithm for positive integers to every pair of
+// integers, following the sign conventions documented on `extended_gcd`.
+#[inline]
+fn __normalized_extended_gcd<T, F>(x: &T, y: &T, positive_gcd: F) -> Gcd<T>
+    where T: Integer + Signed + Clone,
+          F: Fn(&T, &T) -> Gcd<T>
+{
+    let abs_x = x.abs();
+    let abs_y = y.abs();
+
+    let mut result = if abs_y.is_zero() {
+        Gcd {
+            coef_x: if abs_x.is_zero() { T::zero() } else { T::one() },
+            coef_y: T::zero(),
+            g:      T::one(),
+            gcd_xy: abs_x,
+        }
+    } else if abs_x.is_zero() {
+        Gcd {
+            coef_x: T::zero(),
+            coef_y: T::one(),
+            g:      T::one(),
+            gcd_xy: abs_y,
         }
+    } else {
+        positive_gcd(&abs_x, &abs_y)
+    };
 
-        let zero = <BigInt as Zero>::zero();
+    if x.is_negative() {
+        result.coef_x = -result.coef_x;
+    }
 
-        if (*self == zero) || (*y == zero) {
-            return None;
-        }
+    if y.is_negative() {
+        result.coef_y = -result.coef_y;
+    }
 
-        Some(__extended_gcd_bigint(self, y))
+    result
+}
+
+impl ExtendedGcd<BigInt> for BigInt {
+    fn extended_gcd(&self, y: &BigInt) -> Option<Gcd<BigInt>> {
+        Some(__normalized_extended_gcd(self, y, __extended_gcd_bigint))
     }
 
     fn valid_solution(x: &BigInt, y: &BigInt, coef_x: &BigInt, coef_y: &BigInt, gcd_xy: &BigInt) -> bool {
@@ -106,7 +291,7 @@ fn __extended_gcd_bigint(x: &BigInt, y: &BigInt) -> Gcd<BigInt> {
     let mut yy = y.clone();
     let mut g: BigInt = One::one();
 
-    while (&xx).is_even() && (&yy).is_even() {
+    while xx.is_even() && yy.is_even() {
         xx = xx >> 1;
         yy = yy >> 1;
         g  = g << 1;
@@ -120,10 +305,10 @@ fn __extended_gcd_bigint(x: &BigInt, y: &BigInt) -> Gcd<BigInt> {
     let mut d: BigInt = One::one();
 
     loop {
-        while (&u).is_even() {
+        while u.is_even() {
             u = u >> 1;
 
-            if (&a).is_even() && (&b).is_even() {
+            if a.is_even() && b.is_even() {
                 a = a >> 1;
                 b = b >> 1;
             } else {
@@ -132,10 +317,10 @@ fn __extended_gcd_bigint(x: &BigInt, y: &BigInt) -> Gcd<BigInt> {
             }
         }
 
-        while (&v).is_even() {
+        while v.is_even() {
             v = v >> 1;
             
-            if (&c).is_even() && (&d).is_even() {
+            if c.is_even() && d.is_even() {
                 c = c >> 1;
                 d = d >> 1;
             } else {
@@ -160,95 +345,62 @@ fn __extended_gcd_bigint(x: &BigInt, y: &BigInt) -> Gcd<BigInt> {
             return Gcd {
                 coef_x: c,
                 coef_y: d,
-                g:      g,
+                g,
                 gcd_xy: v,
             }
         }
     }
 }
 
-// Panics if x and y are nonpositive.
+// Euclidean extended gcd for primitive types. Panics if x and y are
+// nonpositive. Unlike the binary algorithm, whose coefficients grow to a few
+// times the inputs before shrinking, the coefficients here never exceed
+// y / gcd(x, y) and x / gcd(x, y) in absolute value, so no step can overflow.
 #[inline]
-fn __extended_gcd<T: PrimInt>(x: &T, y: &T) -> Gcd<T> {
+fn __extended_gcd<T: PrimInt + Signed>(x: &T, y: &T) -> Gcd<T> {
     let zero: T = Zero::zero();
-    let mask: T = One::one();
 
     assert!((*x > zero) && (*y > zero));
 
-    let mut xx = *x;
-    let mut yy = *y;
-    let mut g: T = One::one();
-
-    while (xx & mask == zero) && (yy & mask == zero) {
-        xx = xx >> 1;
-        yy = yy >> 1;
-        g  = g << 1;
+    let mut r0: T = *x;
+    let mut r1: T = *y;
+    let mut a0: T = One::one();
+    let mut a1: T = Zero::zero();
+    let mut b0: T = Zero::zero();
+    let mut b1: T = One::one();
+
+    while r1 != zero {
+        let q = r0 / r1;
+
+        let r2 = r0 % r1;
+        let a2 = a0 - q * a1;
+        let b2 = b0 - q * b1;
+
+        r0 = r1;
+        r1 = r2;
+        a0 = a1;
+        a1 = a2;
+        b0 = b1;
+        b1 = b2;
     }
 
-    let mut u: T = xx;
-    let mut v: T = yy;
-    let mut a: T = One::one();
-    let mut b: T = Zero::zero();
-    let mut c: T = Zero::zero();
-    let mut d: T = One::one();
-
-    loop {
-        while u & mask == zero {
-            u = u >> 1;
-
-            if (a & mask == zero) && (b & mask == zero) {
-                a = a >> 1;
-                b = b >> 1;
-            } else {
-                a = (a + yy) >> 1;
-                b = (b - xx) >> 1;
-            }
-        }
-
-        while v & mask == zero {
-            v = v >> 1;
-            
-            if (c & mask == zero) && (d & mask == zero) {
-                c = c >> 1;
-                d = d >> 1;
-            } else {
-                c = (c + yy) >> 1;
-                d = (d - xx) >> 1;
-            }
-        }
-
-        if u >= v {
-            u = u - v;
-            a = a - c;
-            b = b - d;
-        } else {
-            v = v - u;
-            c = c - a;
-            d = d - b;
-        }
-
-        if u == zero {
-            v = g * v;
-
-            return Gcd {
-                coef_x: c,
-                coef_y: d,
-                g:      g,
-                gcd_xy: v,
-            }
-        }
+    Gcd {
+        coef_x: a0,
+        coef_y: b0,
+        g:      One::one(),
+        gcd_xy: r0,
     }
 }
 
 #[inline]
-fn safe_extended_gcd<T: PrimInt>(x: &T, y: &T) -> Option<Gcd<T>> {
-    let zero = <T as Zero>::zero();
+fn safe_extended_gcd<T: PrimInt + Integer + Signed>(x: &T, y: &T) -> Option<Gcd<T>> {
+    let min = <T as Bounded>::min_value();
 
-    if (*x <= zero) || (*y <= zero) {
+    if (*x == min) || (*y == min) {
         return None;
     }
 
-    Some(__extended_gcd(x, y))
+    Some(__normalized_extended_gcd(x, y, __extended_gcd))
 }
 
 #[inline]
@@ -275,12 +427,15 @@ extended_gcd!(i16);
 extended_gcd!(i32);
 extended_gcd!(i64);
 extended_gcd!(isize);
+extended_gcd!(i128);
 
 
 #[cfg(test)]
 mod tests {
-    use num::{BigInt, Num};
-    use super::ExtendedGcd;
+    use num;
+    use num::{BigInt, Num, One, Signed};
+    use super::{ExtendedGcd, Gcd};
+    use error::ModError;
 
 
     struct Test {
@@ -367,6 +522,140 @@ mod tests {
     fn test_extended_gcd() {
         run_gcd_test(&gcd_test_cases());
     }
+
+    #[test]
+    fn test_extended_gcd_signs() {
+        let values: [i64; 7] = [-693, -609, -1, 0, 1, 609, 693];
+
+        for &x in values.iter() {
+            for &y in values.iter() {
+                let result = x.extended_gcd(&y).unwrap();
+                let result_bigint = BigInt::from(x).extended_gcd(&BigInt::from(y)).unwrap();
+
+                assert_eq!(result.gcd_xy, num::integer::gcd(x, y));
+                assert!(result.gcd_xy >= 0);
+                assert!(<i64 as ExtendedGcd<_>>::valid_solution(&x, &y, &result.coef_x, &result.coef_y, &result.gcd_xy));
+                assert_eq!(result_bigint.gcd_xy, BigInt::from(result.gcd_xy));
+
+                // The two algorithms find different coefficients, but agree on
+                // the unique minimal solution.
+                let minimal = result.minimal(&x, &y);
+                let minimal_bigint = result_bigint.minimal(&BigInt::from(x), &BigInt::from(y));
+
+                assert_eq!(minimal_bigint.coef_x, BigInt::from(minimal.coef_x));
+                assert_eq!(minimal_bigint.coef_y, BigInt::from(minimal.coef_y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimal_solution() {
+        for test_case in gcd_test_cases().data.iter() {
+            let (x, y) = (&test_case.x, &test_case.y);
+            let result = x.extended_gcd(y).unwrap().minimal(x, y);
+            let from_test_case = Gcd {
+                coef_x: test_case.coef_x.clone(),
+                coef_y: test_case.coef_y.clone(),
+                g:      BigInt::one(),
+                gcd_xy: test_case.gcd_xy.clone(),
+            }.minimal(x, y);
+
+            assert!(<BigInt as ExtendedGcd<_>>::valid_solution(x, y, &result.coef_x, &result.coef_y, &result.gcd_xy));
+            assert!(result.coef_x.abs() <= y / &result.gcd_xy);
+            assert!(result.coef_y.abs() <= x / &result.gcd_xy);
+            // The minimal solution is unique.
+            assert_eq!(result.coef_x, from_test_case.coef_x);
+            assert_eq!(result.coef_y, from_test_case.coef_y);
+        }
+    }
+
+    #[test]
+    fn test_solution_family() {
+        let (x, y) = (-693i64, 609i64);
+        let result = x.extended_gcd(&y).unwrap();
+        let solutions: Vec<(i64, i64)> = result.solutions(&x, &y).take(5).collect();
+
+        assert_eq!(solutions[0], (7, 8));
+        for &(coef_x, coef_y) in solutions.iter() {
+            assert!(<i64 as ExtendedGcd<_>>::valid_solution(&x, &y, &coef_x, &coef_y, &21));
+        }
+        assert_eq!(result.solution(&x, &y, &0), (result.coef_x, result.coef_y));
+        assert_eq!(0i64.extended_gcd(&0).unwrap().solutions(&0, &0).count(), 1);
+    }
+
+    #[test]
+    fn test_try_extended_gcd() {
+        assert_eq!(693i64.try_extended_gcd(&609).map(|result| result.gcd_xy), Ok(21));
+        assert_eq!(i64::MIN.try_extended_gcd(&609).map(|result| result.gcd_xy), Err(ModError::Overflow));
+        assert_eq!(i128::MAX.try_extended_gcd(&3).map(|result| result.gcd_xy), Ok(1));
+        assert!(BigInt::from(693).try_extended_gcd(&BigInt::from(0)).is_ok());
+    }
+
+    #[test]
+    fn test_extended_gcd_large_primitives() {
+        let pairs: [(i64, i64); 4] = [
+            (0x3fff_ffff_ffff_fff1, 0x2fff_ffff_ffff_ffd3),
+            (3, 0x7fff_ffff_ffff_ffe7),
+            (i64::MAX, i64::MAX - 1),
+            (-i64::MAX, i64::MAX - 2),
+        ];
+
+        for &(x, y) in pairs.iter() {
+            let result = x.extended_gcd(&y).unwrap();
+            let (big_x, big_y) = (BigInt::from(x), BigInt::from(y));
+
+            assert_eq!(result.gcd_xy, num::integer::gcd(x, y));
+            assert!(<BigInt as ExtendedGcd<_>>::valid_solution(&big_x, &big_y,
+                    &BigInt::from(result.coef_x), &BigInt::from(result.coef_y), &BigInt::from(result.gcd_xy)));
+            assert!(result.coef_x.abs() <= (y / result.gcd_xy).abs());
+            assert!(result.coef_y.abs() <= (x / result.gcd_xy).abs());
+        }
+
+        let result = i128::MAX.extended_gcd(&(i128::MAX / 3 * 2)).unwrap();
+
+        assert!(result.coef_x.abs() <= i128::MAX / 3 * 2);
+    }
+
+    #[test]
+    fn test_minimal_solution_large_primitives() {
+        let x: i64 = 1_500_000_000_000_000_001;
+        let y: i64 = 1_000_000_000_000_000_003;
+        let pairs: [(i64, i64); 5] = [(x, y), (-x, y), (x, -y), (i64::MAX, i64::MAX - 1), (i64::MAX, 2)];
+
+        for &(x, y) in pairs.iter() {
+            let minimal = x.extended_gcd(&y).unwrap().minimal(&x, &y);
+            let step = (y / minimal.gcd_xy).abs();
+            let (big_x, big_y) = (BigInt::from(x), BigInt::from(y));
+
+            assert!(<BigInt as ExtendedGcd<_>>::valid_solution(&big_x, &big_y,
+                    &BigInt::from(minimal.coef_x), &BigInt::from(minimal.coef_y), &BigInt::from(minimal.gcd_xy)));
+            assert!((-minimal.coef_x < step + minimal.coef_x) && (minimal.coef_x <= step - minimal.coef_x));
+            assert!(minimal.coef_y.abs() <= (x / minimal.gcd_xy).abs());
+        }
+    }
+
+    #[test]
+    fn test_minimal_solution_zero() {
+        let minimal = 0i64.extended_gcd(&-12).unwrap().minimal(&0, &-12);
+
+        assert_eq!((minimal.gcd_xy, minimal.coef_x, minimal.coef_y), (12, 0, -1));
+
+        let minimal = (-12i64).extended_gcd(&0).unwrap().minimal(&-12, &0);
+
+        assert_eq!((minimal.gcd_xy, minimal.coef_x, minimal.coef_y), (12, -1, 0));
+    }
+
+    #[test]
+    fn test_extended_gcd_zero() {
+        let result = (-12i32).extended_gcd(&0).unwrap();
+
+        assert_eq!((result.gcd_xy, result.coef_x, result.coef_y), (12, -1, 0));
+
+        let result = 0i32.extended_gcd(&0).unwrap();
+
+        assert_eq!((result.gcd_xy, result.coef_x, result.coef_y), (0, 0, 0));
+        assert!(i32::MIN.extended_gcd(&3).is_none());
+    }
 }
 
 #[cfg(test)]
@@ -394,8 +683,8 @@ mod bench {
         Test {
             data: vec![
                 TestCase {
-                    x: x,
-                    y: y,
+                    x,
+                    y,
                 }
             ]
         }
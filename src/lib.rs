@@ -1,10 +1,19 @@
 #![feature(test)]
-pub use modexp::ModExp;
+pub use modexp::{ModExp, Exponent};
 pub use modmult::ModMult;
-pub use extended_gcd::ExtendedGcd;
+pub use extended_gcd::{ExtendedGcd, Gcd, BezoutSolutions};
 pub use modinv::ModInv;
 pub use modadd::ModAdd;
 pub use modsub::ModSub;
+pub use modneg::ModNeg;
+pub use montgomery::{MontgomeryContext, MontgomeryForm, MontgomeryInt};
+pub use barrett::BarrettContext;
+pub use constant_time::{ConstTimeContext, ConstTimeResidue};
+pub use modular::Mod;
+pub use modn::{ModN, ModN32, ModN128};
+pub use residue::{Modulus, Residue};
+pub use shared::{ModContext, SharedMod};
+pub use error::{ModError, ParseModError};
 
 
 extern crate num;
@@ -17,4 +26,16 @@ pub mod extended_gcd;
 pub mod modinv;
 pub mod modadd;
 pub mod modsub;
+pub mod modneg;
 pub mod modular;
+pub mod modn;
+pub mod residue;
+pub mod shared;
+pub mod montgomery;
+pub mod barrett;
+pub mod constant_time;
+pub mod error;
+
+mod wide;
+#[cfg(test)]
+mod testing;
@@ -1,4 +1,5 @@
 use num::{Zero, BigUint, BigInt, Integer, PrimInt};
+use error::{ModError, nonzero_modulus};
 
 
 /// The `ModAdd` trait defines an interface for calculating the sum of two integers 
@@ -10,26 +11,55 @@ pub trait ModAdd {
     /// ```
     /// where x, y, and m are integers, and m is the modulus.
     ///
+    /// Operands outside the range of residues are reduced modulo m first, so
+    /// every input is valid. The result lies in ```[0, m)``` for a positive
+    /// modulus, and in ```(m, 0]``` for a negative one.
+    ///
     /// # Panics
     /// when the modulus is zero.
-    fn mod_add(self: &Self, other: &Self, modulus: &Self) -> Self;
+    fn mod_add(&self, other: &Self, modulus: &Self) -> Self;
+
+    /// Computes ```x + y (mod m)``` like `mod_add`, returning an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// `ModError::ZeroModulus` when the modulus is zero.
+    fn checked_mod_add(&self, other: &Self, modulus: &Self) -> Result<Self, ModError<Self>>
+        where Self: Zero + Sized
+    {
+        nonzero_modulus(modulus).map(|_| self.mod_add(other, modulus))
+    }
 }
 
+// Adds two residues with a conditional subtraction instead of computing x + y
+// directly, so the sum never overflows T.
 #[inline]
-fn __mod_add<T: PrimInt>(x: &T, y: &T, modulus: &T) -> T {
-    assert!(*modulus != <T as Zero>::zero());
+fn __mod_add<T: PrimInt + Integer>(x: &T, y: &T, modulus: &T) -> T {
+    let zero = <T as Zero>::zero();
+
+    assert!(*modulus != zero);
 
-    ((*x) + (*y)) % *modulus
+    let x = x.mod_floor(modulus);
+    let y = y.mod_floor(modulus);
+    // m - y cannot overflow since y lies between 0 and m.
+    let complement = *modulus - y;
+    let wraps = if *modulus > zero { x >= complement } else { x <= complement };
+
+    if wraps {
+        x - complement
+    } else {
+        x + y
+    }
 }
 
 impl ModAdd for BigInt {
-    fn mod_add(self: &BigInt, other: &BigInt, modulus: &BigInt) -> BigInt {
+    fn mod_add(&self, other: &BigInt, modulus: &BigInt) -> BigInt {
         (self + other).mod_floor(modulus)
     }
 }
 
 impl ModAdd for BigUint {
-    fn mod_add(self: &BigUint, other: &BigUint, modulus: &BigUint) -> BigUint {
+    fn mod_add(&self, other: &BigUint, modulus: &BigUint) -> BigUint {
         (self + other).mod_floor(modulus)
     }
 }
@@ -38,7 +68,7 @@ impl ModAdd for BigUint {
 macro_rules! mod_add {
     ( $ T : ty ) => {
         impl ModAdd for $T {
-            fn mod_add(self: &$T, other: &$T, modulus: &$T) -> $T {
+            fn mod_add(&self, other: &$T, modulus: &$T) -> $T {
                 __mod_add(self, other, modulus)
             }
         } 
@@ -51,16 +81,20 @@ mod_add!(u16);
 mod_add!(u32);
 mod_add!(u64);
 mod_add!(usize);
+mod_add!(u128);
 mod_add!(i8);
 mod_add!(i16);
 mod_add!(i32);
 mod_add!(i64);
 mod_add!(isize);
+mod_add!(i128);
 
 #[cfg(test)]
 mod tests {
+    use testing::check_signed_matches_bigint;
     use num::BigInt;
     use super::ModAdd;
+    use error::ModError;
 
 
     struct TestCase {
@@ -118,6 +152,35 @@ mod tests {
         run_tests(&mod_add_test_cases());
     }
 
+    #[test]
+    fn test_mod_add_no_overflow() {
+        assert_eq!(0xfffffffffffffffeu64.mod_add(&0xfffffffffffffffd, &0xffffffffffffffff), 0xfffffffffffffffc);
+        assert_eq!(250u8.mod_add(&10, &251), 9);
+        assert_eq!(255u8.mod_add(&255, &251), 8);
+        assert_eq!(i64::MAX.mod_add(&(i64::MAX - 1), &i64::MAX), i64::MAX - 1);
+        assert_eq!(3i32.mod_add(&4, &-5), -3);
+    }
+
+    #[test]
+    fn test_mod_add_signed_matches_bigint() {
+        check_signed_matches_bigint(|x, y, modulus| x.mod_add(&y, &modulus), |x, y, modulus| x.mod_add(y, modulus));
+    }
+
+    #[test]
+    fn test_mod_add_128_no_overflow() {
+        assert_eq!((u128::MAX - 1).mod_add(&(u128::MAX - 2), &u128::MAX), u128::MAX - 3);
+        assert_eq!(i128::MAX.mod_add(&i128::MAX, &i128::MIN), -2);
+        assert_eq!(i128::MIN.mod_add(&-1, &i128::MAX), i128::MAX - 2);
+    }
+
+    #[test]
+    fn test_checked_mod_add() {
+        assert_eq!(3u64.checked_mod_add(&5, &7), Ok(1));
+        assert_eq!(3i32.checked_mod_add(&5, &-7), Ok(-6));
+        assert_eq!(3i32.checked_mod_add(&5, &0), Err(ModError::ZeroModulus));
+        assert_eq!(BigInt::from(3).checked_mod_add(&BigInt::from(5), &BigInt::from(0)), Err(ModError::ZeroModulus));
+    }
+
     #[test]
     #[should_panic]
     fn test_mod_add_zero_mod() {
@@ -1,5 +1,252 @@
 use num::{Integer, Zero, One, PrimInt, BigInt, BigUint};
+use num::bigint::Sign;
+use barrett::BarrettContext;
+use montgomery::MontgomeryContext;
+use modmult::ModMult;
+use modinv::ModInv;
+use error::{ModError, nonzero_modulus, positive_modulus};
+
+
+/// The `Exponent` trait abstracts over the types an exponent of `mod_exp` can
+/// take: the primitive integers, `BigUint` and `BigInt`, and sequences of bits
+/// stored least significant bit first, such as one collected from a bit
+/// iterator into a ```Vec<bool>```.
+///
+/// The bits of a signed exponent are the bits of its absolute value.
+pub trait Exponent {
+    /// Returns whether the exponent is negative.
+    fn is_negative(&self) -> bool;
+
+    /// Returns the number of significant bits of the exponent, which is zero
+    /// for an exponent of zero.
+    fn bits(&self) -> usize;
+
+    /// Returns bit i of the exponent, counting from the least significant bit.
+    ///
+    /// For `BigUint` and `BigInt` each call converts the whole magnitude, so
+    /// reading every bit should go through `bits_le` instead.
+    fn bit(&self, i: usize) -> bool;
+
+    /// Returns the significant bits of the exponent, least significant bit
+    /// first, so that ```bits_le().len() == bits()```.
+    fn bits_le(&self) -> Vec<bool> {
+        (0..self.bits()).map(|i| self.bit(i)).collect()
+    }
+}
+
+// Macro for implementations of Exponent for unsigned primitive types.
+macro_rules! exponent_unsigned {
+    ( $ T : ty ) => {
+        impl Exponent for $T {
+            fn is_negative(&self) -> bool {
+                false
+            }
+
+            fn bits(&self) -> usize {
+                (<$T>::BITS - self.leading_zeros()) as usize
+            }
+
+            fn bit(&self, i: usize) -> bool {
+                (i < <$T>::BITS as usize) && ((*self >> i) & 1 == 1)
+            }
+        }
+    }
+}
+
+exponent_unsigned!(u8);
+exponent_unsigned!(u16);
+exponent_unsigned!(u32);
+exponent_unsigned!(u64);
+exponent_unsigned!(usize);
+exponent_unsigned!(u128);
+
+// Macro for implementations of Exponent for signed primitive types.
+macro_rules! exponent_signed {
+    ( $ T : ty ) => {
+        impl Exponent for $T {
+            fn is_negative(&self) -> bool {
+                *self < 0
+            }
+
+            fn bits(&self) -> usize {
+                self.unsigned_abs().bits()
+            }
+
+            fn bit(&self, i: usize) -> bool {
+                self.unsigned_abs().bit(i)
+            }
+        }
+    }
+}
+
+exponent_signed!(i8);
+exponent_signed!(i16);
+exponent_signed!(i32);
+exponent_signed!(i64);
+exponent_signed!(isize);
+exponent_signed!(i128);
+
+// Bit i of a magnitude stored as little endian bytes.
+#[inline]
+fn __byte_bit(bytes: &[u8], i: usize) -> bool {
+    bytes.get(i / 8).is_some_and(|byte| (byte >> (i % 8)) & 1 == 1)
+}
+
+// The first bits bits of a magnitude stored as little endian bytes.
+#[inline]
+fn __byte_bits_le(bytes: &[u8], bits: usize) -> Vec<bool> {
+    (0..bits).map(|i| __byte_bit(bytes, i)).collect()
+}
+
+impl Exponent for BigUint {
+    fn is_negative(&self) -> bool {
+        false
+    }
+
+    fn bits(&self) -> usize {
+        BigUint::bits(self)
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        __byte_bit(&self.to_bytes_le(), i)
+    }
+
+    fn bits_le(&self) -> Vec<bool> {
+        __byte_bits_le(&self.to_bytes_le(), BigUint::bits(self))
+    }
+}
+
+impl Exponent for BigInt {
+    fn is_negative(&self) -> bool {
+        self.sign() == Sign::Minus
+    }
+
+    fn bits(&self) -> usize {
+        BigInt::bits(self)
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        __byte_bit(&self.to_bytes_le().1, i)
+    }
+
+    fn bits_le(&self) -> Vec<bool> {
+        __byte_bits_le(&self.to_bytes_le().1, BigInt::bits(self))
+    }
+}
+
+impl Exponent for [bool] {
+    fn is_negative(&self) -> bool {
+        false
+    }
+
+    fn bits(&self) -> usize {
+        self.iter().rposition(|&bit| bit).map_or(0, |i| i + 1)
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.get(i).cloned().unwrap_or(false)
+    }
+}
+
+impl Exponent for Vec<bool> {
+    fn is_negative(&self) -> bool {
+        false
+    }
+
+    fn bits(&self) -> usize {
+        self[..].bits()
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self[..].bit(i)
+    }
+}
+
+// Window size for sliding window exponentiation with an exponent of the given
+// bit length. Larger windows trade a table of 2^(k-1) odd powers of the base
+// for fewer multiplications; these thresholds follow the usual choice of k
+// minimizing the total count.
+#[inline]
+fn __window_size(bits: usize) -> usize {
+    match bits {
+        0..=23    => 1,
+        24..=79   => 3,
+        80..=239  => 4,
+        240..=671 => 5,
+        _         => 6,
+    }
+}
+
+// Left to right sliding window exponentiation. The multiplication mult is
+// that of the representation the caller works in, such as Montgomery form,
+// and one is the representation of one. The sign of the exponent is ignored,
+// and its bits are read once up front.
+//
+// See Algorithm 14.85 of the 'Handbook of Applied Cryptography'.
+pub(crate) fn sliding_window_exp<T, E, F>(one: T, base: T, exponent: &E, mult: F) -> T
+    where T: Clone,
+          E: Exponent + ?Sized,
+          F: Fn(&T, &T) -> T
+{
+    let exponent = exponent.bits_le();
+    let bits = exponent.len();
+
+    if bits == 0 {
+        return one;
+    }
+
+    let k = __window_size(bits);
+
+    // The odd powers base, base^3, ..., base^(2^k - 1).
+    let base_squared = mult(&base, &base);
+    let mut table = Vec::with_capacity(1 << (k - 1));
+    table.push(base);
+    for i in 1..(1 << (k - 1)) {
+        let next = mult(&table[i - 1], &base_squared);
+        table.push(next);
+    }
+
+    let mut result = one;
+    let mut started = false;
+    // One past the highest bit not yet processed.
+    let mut i = bits;
+
+    while i > 0 {
+        if !exponent[i - 1] {
+            result = mult(&result, &result);
+            i -= 1;
+            continue;
+        }
+
+        // The longest window of at most k bits from bit i - 1 down that ends
+        // in a one bit.
+        let mut low = i.saturating_sub(k);
+        while !exponent[low] {
+            low += 1;
+        }
+
+        let mut window = 0;
+        for j in (low..i).rev() {
+            window = (window << 1) | (exponent[j] as usize);
+        }
+
+        if started {
+            for _ in low..i {
+                result = mult(&result, &result);
+            }
+            result = mult(&result, &table[window >> 1]);
+        } else {
+            // Squaring one is wasted work, so the first window is read
+            // straight from the table.
+            result = table[window >> 1].clone();
+            started = true;
+        }
 
+        i = low;
+    }
+
+    result
+}
 
 /// The `ModExp` trait defines an interface for modular exponentiation.
 pub trait ModExp {
@@ -7,10 +254,18 @@ pub trait ModExp {
     /// ```text
     /// b ^ e (mod m)
     /// ```
-    /// where b is the base, e is the exponent, and m is the modulus.
+    /// where b is the base, e is the exponent, and m is the modulus. The
+    /// exponent may be of any type implementing `Exponent`, so a `u8` can be
+    /// raised to a `u64` power, or a `BigInt` to a `u32` power. A negative
+    /// exponent raises the inverse of b to ```|e|```.
+    ///
+    /// The running time depends on the bits of the exponent, so secret
+    /// exponents should go through `ConstTimeContext` instead.
     ///
     /// # Panics
-    /// when a modulus of zero is passed.
+    /// when a modulus of zero or a negative modulus is passed, or the exponent
+    /// is negative and b has no inverse modulo m. `checked_mod_exp` returns
+    /// these as errors instead.
     ///
     /// # Examples
     /// 
@@ -27,87 +282,147 @@ pub trait ModExp {
     ///     let modulus  = BigInt::from(29);
     ///     let exp      = base.mod_exp(&exponent, &modulus);
     ///
-    ///     println!("{}", exp);
+    ///     assert_eq!(base.mod_exp(&8u64, &modulus), exp);
+    ///     assert_eq!(3u8.mod_exp(&(1u64 << 40), &251), 88);
+    ///     assert_eq!(17i32.mod_exp(&-3, &29), 17);
     /// }
     /// ```
     ///
-    fn mod_exp(self: &Self, exponent: &Self, modulus: &Self) -> Self;
+    fn mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &Self) -> Self;
+
+    /// Computes ```b ^ e (mod m)``` like `mod_exp`, returning an error instead
+    /// of panicking.
+    ///
+    /// The default implementation only checks the modulus before calling
+    /// `mod_exp`; the implementations in this crate also report a base with
+    /// no inverse.
+    ///
+    /// # Errors
+    /// `ModError::ZeroModulus` when the modulus is zero,
+    /// `ModError::NegativeInput` when the modulus is negative, and
+    /// `ModError::NotInvertible` with the common factor of b and m when the
+    /// exponent is negative and b has no inverse.
+    fn checked_mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &Self) -> Result<Self, ModError<Self>>
+        where Self: Zero + PartialOrd + Sized
+    {
+        positive_modulus(modulus).map(|_| self.mod_exp(exponent, modulus))
+    }
 }
 
+// Replaces the base by its inverse for a negative exponent, since the loops
+// below only read the bits of the absolute value of the exponent.
 #[inline]
-fn __mod_exp<T: PrimInt>(base: &T, exponent: &T, modulus: &T) -> T {
-    let zero: T = Zero::zero();
+fn __exp_base<T, E>(base: &T, exponent: &E, modulus: &T) -> Result<T, ModError<T>>
+    where T: Clone + Integer + ModInv<T>,
+          E: Exponent + ?Sized
+{
+    if exponent.is_negative() {
+        base.try_mod_inv(modulus)
+    } else {
+        Ok(base.clone())
+    }
+}
 
-    assert!(*modulus != zero);
+#[inline]
+fn __mod_exp<T, E>(base: &T, exponent: &E, modulus: &T) -> Result<T, ModError<T>>
+    where T: PrimInt + Integer + ModMult + ModInv<T>,
+          E: Exponent + ?Sized
+{
+    positive_modulus(modulus)?;
 
-    let one = One::one();
-    let two = one + one;
+    let zero: T = Zero::zero();
+    let one: T = One::one();
 
     if *modulus == one {
-        return zero;
+        return Ok(zero);
     }
 
     let mut result = one;
-    let mut modded_base = *base % *modulus;
-    let mut divided_exponent = *exponent;
-        
-    while divided_exponent > zero {
-        if divided_exponent % two == one {
-            result = result * modded_base % *modulus;
+    let mut modded_base = __exp_base(base, exponent, modulus)?.mod_floor(modulus);
+
+    for bit in exponent.bits_le() {
+        if bit {
+            result = result.mod_mult(&modded_base, modulus);
         }
-        divided_exponent = divided_exponent >> 1;
-        modded_base = modded_base * modded_base % *modulus;
+        modded_base = modded_base.mod_mult(&modded_base, modulus);
     }
 
     assert!(result < *modulus);
 
-    result
+    Ok(result)
 }
 
-// Implementation of the ModExp trait for BigInt and BigUint.
-macro_rules! mod_exp_bignum {
-    ( $ T : ty ) => {
-        impl ModExp for $T {
-            fn mod_exp(self: &$T, exponent: &$T, modulus: &$T) -> $T {
-                let zero: $T = <$T as Zero>::zero();
+#[inline]
+fn __mod_exp_bigint<E>(base: &BigInt, exponent: &E, modulus: &BigInt) -> Result<BigInt, ModError<BigInt>>
+    where E: Exponent + ?Sized
+{
+    positive_modulus(modulus)?;
 
-                assert!(*modulus != zero);
+    // The modulus is positive, so the reduced base and the modulus convert to
+    // BigUint for Montgomery or Barrett reduction.
+    let base = __exp_base(base, exponent, modulus)?.mod_floor(modulus);
+    let result = __mod_exp_biguint_unsigned(&base.to_biguint().unwrap(), exponent, &modulus.to_biguint().unwrap());
 
-                let one: $T = <$T as One>::one();
-                let two: $T = <$T as One>::one() + <$T as One>::one();
+    Ok(BigInt::from_biguint(Sign::Plus, result))
+}
 
-                if *modulus == one {
-                    return zero;
-                }
+impl ModExp for BigInt {
+    fn mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &BigInt) -> BigInt {
+        __mod_exp_bigint(self, exponent, modulus).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-                let mut result: $T = <$T as One>::one();
-                let mut base: $T   = self.mod_floor(modulus);
-                let mut divided_exponent: $T = exponent.clone();
-        
-                while divided_exponent > zero {
-                    if divided_exponent.mod_floor(&two) == one {
-                        result = (&result * &base).mod_floor(modulus);
-                    }
-                    divided_exponent = divided_exponent >> 1;
-                    base = (&base * &base).mod_floor(modulus);
-                }
+    fn checked_mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &BigInt) -> Result<BigInt, ModError<BigInt>> {
+        __mod_exp_bigint(self, exponent, modulus)
+    }
+}
 
-                assert!(result < *modulus);
+#[inline]
+fn __mod_exp_biguint<E>(base: &BigUint, exponent: &E, modulus: &BigUint) -> Result<BigUint, ModError<BigUint>>
+    where E: Exponent + ?Sized
+{
+    nonzero_modulus(modulus)?;
 
-                result
-            }
-        }
+    let base = __exp_base(base, exponent, modulus)?;
+
+    Ok(__mod_exp_biguint_unsigned(&base, exponent, modulus))
+}
+
+// Computes b ^ |e| (mod m) for a nonzero modulus, in Montgomery form for an
+// odd modulus and with Barrett reduction otherwise.
+#[inline]
+fn __mod_exp_biguint_unsigned<E>(base: &BigUint, exponent: &E, modulus: &BigUint) -> BigUint
+    where E: Exponent + ?Sized
+{
+    if modulus.is_odd() && !modulus.is_one() {
+        let ctx = MontgomeryContext::new(modulus).unwrap();
+
+        ctx.mod_exp_abs(&base.mod_floor(modulus), exponent)
+    } else {
+        let ctx = BarrettContext::new(modulus).unwrap();
+
+        ctx.mod_exp_abs(base, exponent)
     }
 }
 
-mod_exp_bignum!(BigInt);
-mod_exp_bignum!(BigUint);
+impl ModExp for BigUint {
+    fn mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &BigUint) -> BigUint {
+        __mod_exp_biguint(self, exponent, modulus).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn checked_mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &BigUint) -> Result<BigUint, ModError<BigUint>> {
+        __mod_exp_biguint(self, exponent, modulus)
+    }
+}
 
 // Macro for implementations of ModExp trait.
 macro_rules! mod_exp {
     ( $ T : ty ) => {
         impl ModExp for $T {
-            fn mod_exp(self: &$T, exponent: &$T, modulus: &$T) -> $T {
+            fn mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &$T) -> $T {
+                __mod_exp(self, exponent, modulus).unwrap_or_else(|err| panic!("{}", err))
+            }
+
+            fn checked_mod_exp<E: Exponent + ?Sized>(&self, exponent: &E, modulus: &$T) -> Result<$T, ModError<$T>> {
                 __mod_exp(self, exponent, modulus)
             }
         }  
@@ -119,17 +434,21 @@ mod_exp!(u16);
 mod_exp!(u32);
 mod_exp!(u64);
 mod_exp!(usize);
+mod_exp!(u128);
 mod_exp!(i8);
 mod_exp!(i16);
 mod_exp!(i32);
 mod_exp!(i64);
 mod_exp!(isize);
+mod_exp!(i128);
 
 
 #[cfg(test)]
 mod tests {
-    use super::ModExp;
-    use num::{BigInt, Num};
+    use super::{ModExp, Exponent};
+    use error::ModError;
+    use num;
+    use num::{BigInt, BigUint, Num};
     use std::io::Write;
     use std::io;
 
@@ -382,9 +701,6 @@ mod tests {
         let base     = BigInt::from(11);
 
         base.mod_exp(&exponent, &modulus);
-
-        // mod_exp did not panic.
-        assert!(false);
     }
 
     #[test]
@@ -499,9 +815,114 @@ mod tests {
         run_test_i(&test_cases_small_integers_i());
     }
 
+    #[test]
+    fn test_mod_exp_128() {
+        let p: u128 = (1 << 127) - 1;
+
+        // Fermat's little theorem for the Mersenne prime 2^127 - 1.
+        assert_eq!(3u128.mod_exp(&(p - 1), &p), 1);
+        assert_eq!(u128::MAX.mod_exp(&65537, &0xfffffffffffffffffffffffffffffff1), 0x5ae0baa10e68b4bbda895cede14834f5);
+        assert_eq!((-3i128).mod_exp(&3, &(p as i128)), p as i128 - 27);
+    }
+
+    #[test]
+    fn test_checked_mod_exp() {
+        assert_eq!(17u32.checked_mod_exp(&8, &29), Ok(1));
+        assert_eq!(17i32.checked_mod_exp(&8, &0), Err(ModError::ZeroModulus));
+        assert_eq!(17i32.checked_mod_exp(&-3, &29), Ok(17));
+        assert_eq!(BigInt::from(17).checked_mod_exp(&BigInt::from(-3), &BigInt::from(29)), Ok(BigInt::from(17)));
+        assert_eq!(6u32.checked_mod_exp(&-1, &9), Err(ModError::NotInvertible { gcd: 3 }));
+        assert_eq!(BigInt::from(6).checked_mod_exp(&-2, &BigInt::from(9)), Err(ModError::NotInvertible { gcd: BigInt::from(3) }));
+        assert_eq!(BigUint::from(6u32).checked_mod_exp(&BigUint::from(2u32), &BigUint::from(0u32)), Err(ModError::ZeroModulus));
+    }
+
+    #[test]
+    fn test_checked_mod_exp_negative_modulus() {
+        assert_eq!(2i64.checked_mod_exp(&3, &-7), Err(ModError::NegativeInput));
+        assert_eq!(2i64.checked_mod_exp(&-3, &-7), Err(ModError::NegativeInput));
+        assert_eq!(3i128.checked_mod_exp(&5, &i128::MIN), Err(ModError::NegativeInput));
+        assert_eq!(BigInt::from(2).checked_mod_exp(&3, &BigInt::from(-7)), Err(ModError::NegativeInput));
+        assert_eq!(BigInt::from(6).checked_mod_exp(&-1, &BigInt::from(-9)), Err(ModError::NegativeInput));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_exp_should_panic_with_non_invertible_base() {
+        6i64.mod_exp(&-1, &9);
+    }
+
+    #[test]
+    fn test_mod_exp_u64_large_modulus() {
+        let modulus: u64 = 0xffffffffffffffc5;
+        let base: u64 = 0xfedcba9876543210;
+        let exponent: u64 = 0x123456789abcdef;
+
+        assert_eq!(base.mod_exp(&exponent, &modulus), 10464253078750218099);
+    }
+
+    #[test]
+    fn test_mod_exp_mixed_exponents() {
+        let base = <BigInt as Num>::from_str_radix("12345678901234567890123", 10).unwrap();
+        let modulus = <BigInt as Num>::from_str_radix("1000000000000000000000000000057", 10).unwrap();
+        let expected = <BigInt as Num>::from_str_radix("48866994262318935139557631587", 10).unwrap();
+        let exponent: u64 = (1 << 63) + 5;
+
+        assert_eq!(base.mod_exp(&exponent, &modulus), expected);
+        assert_eq!(base.mod_exp(&BigUint::from(exponent), &modulus), expected);
+        assert_eq!(3u8.mod_exp(&(1u64 << 40), &251), 88);
+        assert_eq!(3u8.mod_exp(&BigUint::from(1u64 << 40), &251), 88);
+        assert_eq!(17u32.mod_exp(&8u8, &29), 1);
+    }
+
+    #[test]
+    fn test_sliding_window_matches_modpow() {
+        let large = num::pow(BigUint::from(3u32), 1500);
+        let odd_modulus = num::pow(BigUint::from(3u32), 400) + BigUint::from(2u32);
+        let even_modulus = num::pow(BigUint::from(7u32), 300) + BigUint::from(1u32);
+        let base = num::pow(BigUint::from(5u32), 350);
+
+        // Exponent lengths on either side of each change of window size.
+        for &bits in [1, 2, 5, 23, 24, 79, 80, 239, 240, 671, 672, 1024, 2000].iter() {
+            let exponent = &large >> (large.bits() - bits);
+
+            for modulus in [&odd_modulus, &even_modulus].iter() {
+                assert_eq!(base.mod_exp(&exponent, modulus), base.modpow(&exponent, modulus));
+            }
+        }
+    }
+
+    #[test]
+    fn test_mod_exp_bit_exponents() {
+        // 0b1011001 == 89, least significant bit first.
+        let bits: Vec<bool> = [1, 0, 0, 1, 1, 0, 1, 0, 0].iter().map(|&bit| bit == 1).collect();
+
+        assert_eq!(bits.bits(), 7);
+        assert_eq!(7u64.mod_exp(&bits, &1000003), 728468);
+        assert_eq!(7u64.mod_exp(&bits[..], &1000003), 7u64.mod_exp(&89u8, &1000003));
+        assert_eq!(7u64.mod_exp(&Vec::new(), &1000003), 1);
+        assert_eq!(7u64.checked_mod_exp(&-89i64, &1000003), Ok(982554));
+    }
+
+    #[test]
+    fn test_exponent_bits_le() {
+        let bits: Vec<bool> = [1, 0, 0, 1, 1, 0, 1].iter().map(|&bit| bit == 1).collect();
+
+        assert_eq!(89u8.bits_le(), bits);
+        assert_eq!((-89i64).bits_le(), bits);
+        assert_eq!(BigUint::from(89u32).bits_le(), bits);
+        assert_eq!(BigInt::from(-89).bits_le(), bits);
+        assert_eq!([true, false, false][..].bits_le(), vec![true]);
+        assert!(BigUint::from(0u32).bits_le().is_empty());
+
+        let large = num::pow(BigUint::from(3u32), 500);
+        let expected: Vec<bool> = (0..large.bits()).map(|i| large.bit(i)).collect();
+
+        assert_eq!(large.bits_le(), expected);
+    }
+
 }
 
-#[cfg(all(test))]
+#[cfg(test)]
 mod bench {
     use num::{Num, BigInt};
     use super::ModExp;
@@ -541,9 +962,9 @@ mod bench {
         Test {
             data: vec![
                 TestCase {
-                    base: base,
-                    exponent: exponent,
-                    modulus: modulus,
+                    base,
+                    exponent,
+                    modulus,
                 }
             ]
         }
@@ -1,5 +1,8 @@
-use num::{Zero, One, BigInt, PrimInt};
+use num::{Zero, One, BigInt, BigUint};
 use extended_gcd::ExtendedGcd;
+use modsub::ModSub;
+use modmult::ModMult;
+use error::{ModError, nonzero_modulus};
 
 
 /// The `ModInv` trait defines an interface for computing the inverse of
@@ -13,6 +16,9 @@ pub trait ModInv<T> {
     /// ```text
     /// x * y == 1 (mod m)
     /// ```
+    /// The value to be inverted may lie outside ```[0, m)```, including negative
+    /// values for signed types; it is reduced modulo m first.
+    ///
     /// # Safety
     /// The function `mod_inv` returns None when it is passed a modulus 
     /// of zero, or the value to be inverted does not have an inverse.
@@ -36,41 +42,55 @@ pub trait ModInv<T> {
     /// ```
     ///
     fn mod_inv(&self, modulus: &T) -> Option<T>;
+
+    /// Computes the modular inverse of an integer x like `mod_inv`, reporting
+    /// why there is none.
+    ///
+    /// The default implementation calls `mod_inv` and recovers the common
+    /// factor of x and m with `gcd` when it fails; the implementations in this
+    /// crate compute both in one pass.
+    ///
+    /// # Errors
+    /// `ModError::ZeroModulus` when the modulus is zero,
+    /// `ModError::NotInvertible` with the common factor of x and m when x has
+    /// no inverse, and `ModError::Overflow` when the modulus is the minimum of
+    /// a signed type.
+    fn try_mod_inv(&self, modulus: &T) -> Result<T, ModError<T>>
+        where Self: Clone + Into<T>,
+              T: ::num::Integer + Clone
+    {
+        nonzero_modulus(modulus)?;
+
+        self.mod_inv(modulus).ok_or_else(|| {
+            ModError::NotInvertible { gcd: self.clone().into().gcd(modulus) }
+        })
+    }
 }
 
 impl ModInv<BigInt> for BigInt {
     fn mod_inv(&self, modulus: &BigInt) -> Option<BigInt> {
-        let result = self.extended_gcd(modulus);
-        
-        result.and_then(|gcd_result| {
-            if gcd_result.gcd_xy == One::one() {
-                if gcd_result.coef_x >= Zero::zero() {
-                    Some(gcd_result.coef_x)
-                } else {
-                    Some(modulus + gcd_result.coef_x)
-                }
-            } else {
-                None
-            }
-        })
+        __mod_inv(self, modulus).ok()
+    }
+
+    fn try_mod_inv(&self, modulus: &BigInt) -> Result<BigInt, ModError<BigInt>> {
+        __mod_inv(self, modulus)
     }
 }
 
+// The value is reduced first, so that only a modulus whose absolute value is
+// not representable can make the extended gcd fail.
 #[inline]
-fn __mod_inv<T>(x: &T, modulus: &T) -> Option<T> where T: PrimInt + ExtendedGcd<T> {
-    let result = x.extended_gcd(modulus);
-    
-    result.and_then(|gcd_result| {
-        if gcd_result.gcd_xy == One::one() {
-            if gcd_result.coef_x >= Zero::zero() {
-                Some(gcd_result.coef_x)
-            } else {
-                Some(*modulus + gcd_result.coef_x)
-            }
-        } else {
-            None
-        }
-    })
+fn __mod_inv<T>(x: &T, modulus: &T) -> Result<T, ModError<T>> where T: ::num::Integer + Clone + ExtendedGcd<T> {
+    nonzero_modulus(modulus)?;
+
+    let x = x.mod_floor(modulus);
+    let gcd_result = <T as ExtendedGcd<T>>::extended_gcd(&x, modulus).ok_or(ModError::Overflow)?;
+
+    if gcd_result.gcd_xy.is_one() {
+        Ok(gcd_result.coef_x.mod_floor(modulus))
+    } else {
+        Err(ModError::NotInvertible { gcd: gcd_result.gcd_xy })
+    }
 }
 
 // macro for defining ModInv implementations.
@@ -78,6 +98,10 @@ macro_rules! mod_inv {
     ( $ T : ty ) => {
         impl ModInv<$T> for $T {
             fn mod_inv(&self, modulus: &$T) -> Option<$T> {
+                __mod_inv(self, modulus).ok()
+            }
+
+            fn try_mod_inv(&self, modulus: &$T) -> Result<$T, ModError<$T>> {
                 __mod_inv(self, modulus)
             }
         }
@@ -89,12 +113,78 @@ mod_inv!(i16);
 mod_inv!(i32);
 mod_inv!(i64);
 mod_inv!(isize);
+mod_inv!(i128);
+
+// Extended Euclidean algorithm for unsigned types. Only the coefficient of x
+// is tracked, and it is kept reduced modulo m, so the signed intermediate
+// values of the textbook algorithm never appear.
+#[inline]
+fn __unsigned_mod_inv<T>(x: &T, modulus: &T) -> Result<T, ModError<T>>
+    where T: ::num::Integer + Clone + ModSub + ModMult
+{
+    nonzero_modulus(modulus)?;
+
+    let mut r0 = modulus.clone();
+    let mut r1 = x.mod_floor(modulus);
+    let mut t0: T = Zero::zero();
+    let mut t1: T = One::one();
+
+    while !r1.is_zero() {
+        let (q, r2) = r0.div_rem(&r1);
+        let t2 = t0.mod_sub(&q.mod_mult(&t1, modulus), modulus);
+
+        r0 = r1;
+        r1 = r2;
+        t0 = t1;
+        t1 = t2;
+    }
+
+    if r0.is_one() {
+        Ok(t0.mod_floor(modulus))
+    } else {
+        Err(ModError::NotInvertible { gcd: r0 })
+    }
+}
+
+impl ModInv<BigUint> for BigUint {
+    fn mod_inv(&self, modulus: &BigUint) -> Option<BigUint> {
+        __unsigned_mod_inv(self, modulus).ok()
+    }
+
+    fn try_mod_inv(&self, modulus: &BigUint) -> Result<BigUint, ModError<BigUint>> {
+        __unsigned_mod_inv(self, modulus)
+    }
+}
+
+// macro for defining ModInv implementations for unsigned types.
+macro_rules! mod_inv_unsigned {
+    ( $ T : ty ) => {
+        impl ModInv<$T> for $T {
+            fn mod_inv(&self, modulus: &$T) -> Option<$T> {
+                __unsigned_mod_inv(self, modulus).ok()
+            }
+
+            fn try_mod_inv(&self, modulus: &$T) -> Result<$T, ModError<$T>> {
+                __unsigned_mod_inv(self, modulus)
+            }
+        }
+    }
+}
+
+mod_inv_unsigned!(u8);
+mod_inv_unsigned!(u16);
+mod_inv_unsigned!(u32);
+mod_inv_unsigned!(u64);
+mod_inv_unsigned!(usize);
+mod_inv_unsigned!(u128);
 
 
 #[cfg(test)]
 mod tests {
-    use num::{One, Integer, BigInt, Num};
+    use num::{One, Integer, BigInt, BigUint, Num};
     use super::ModInv;
+    use modmult::ModMult;
+    use error::ModError;
     use std::fmt::Debug;
 
 
@@ -237,11 +327,136 @@ mod tests {
         run_tests(&isize_test_cases());
     }
 
+    fn u64_test_cases() -> Test<u64> {
+        Test {
+            data: vec![
+                TestCase {
+                    a:       633,
+                    a_inv:   177,
+                    modulus: 2801,
+                },
+                TestCase {
+                    a:       39357,
+                    a_inv:   10218,
+                    modulus: 33695,
+                },
+                TestCase {
+                    a:       0xfffffffffffffffe,
+                    a_inv:   0xfffffffffffffffe,
+                    modulus: 0xffffffffffffffff,
+                }
+            ]
+        }
+    }
+
+    fn biguint_test_cases() -> Test<BigUint> {
+        Test {
+            data: bigint_test_cases().data.iter().map(|test_case| {
+                TestCase {
+                    a:       test_case.a.to_biguint().unwrap(),
+                    a_inv:   test_case.a_inv.to_biguint().unwrap(),
+                    modulus: test_case.modulus.to_biguint().unwrap(),
+                }
+            }).collect()
+        }
+    }
+
+    fn non_invertible_u8_test_cases() -> NonInvTest<u8> {
+        NonInvTest {
+            data: vec![
+                NonInvTestCase {
+                    a:       6,
+                    modulus: 250,
+                },
+                NonInvTestCase {
+                    a:       3,
+                    modulus: 0,
+                }
+            ]
+        }
+    }
+
+    #[test]
+    fn test_mod_inverse_u64() {
+        run_tests(&u64_test_cases());
+    }
+
+    #[test]
+    fn test_mod_inverse_biguint() {
+        run_tests(&biguint_test_cases());
+    }
+
+    #[test]
+    fn test_non_invertible_u8() {
+        run_non_inv_tests(&non_invertible_u8_test_cases());
+    }
+
+    #[test]
+    fn test_mod_inverse_negative() {
+        assert_eq!((-633isize).mod_inv(&2801), Some(2801 - 177));
+        assert_eq!(BigInt::from(-271).mod_inv(&BigInt::from(383)), Some(BigInt::from(383 - 106)));
+        assert_eq!(5i32.mod_inv(&0), None);
+    }
+
     #[test]
     fn test_non_invertible() {
         run_non_inv_tests(&non_invertible_isize_test_cases());
     }
 
+    #[test]
+    fn test_mod_inverse_128() {
+        let p: u128 = (1 << 127) - 1;
+        let x: u128 = 0xdeadbeefcafebabe0123456789abcdef;
+
+        assert_eq!(x.mod_inv(&p).map(|x_inv| x.mod_mult(&x_inv, &p)), Some(1));
+        assert_eq!((-(x as i128)).mod_inv(&(p as i128)).map(|x_inv| x_inv.mod_mult(&-(x as i128), &(p as i128))), Some(1));
+        assert_eq!(6u128.mod_inv(&(1 << 100)), None);
+        assert_eq!(3i128.try_mod_inv(&i128::MIN), Err(ModError::Overflow));
+    }
+
+    #[test]
+    fn test_mod_inverse_large_signed_modulus() {
+        assert_eq!(3i64.mod_inv(&0x7fff_ffff_ffff_ffe7), Some(6148914691236517189));
+        assert_eq!(3i64.mod_inv(&-0x7fff_ffff_ffff_ffe7), Some(6148914691236517189 - 0x7fff_ffff_ffff_ffe7));
+    }
+
+    #[test]
+    fn test_try_mod_inv() {
+        assert_eq!(17i64.try_mod_inv(&29), Ok(12));
+        assert_eq!(12u8.try_mod_inv(&30), Err(ModError::NotInvertible { gcd: 6 }));
+        assert_eq!((-12i32).try_mod_inv(&30), Err(ModError::NotInvertible { gcd: 6 }));
+        assert_eq!(12u32.try_mod_inv(&0), Err(ModError::ZeroModulus));
+        assert_eq!(3i64.try_mod_inv(&i64::MIN), Err(ModError::Overflow));
+        assert_eq!(BigUint::from(12u32).try_mod_inv(&BigUint::from(30u32)), Err(ModError::NotInvertible { gcd: BigUint::from(6u32) }));
+        assert_eq!(BigInt::from(12).try_mod_inv(&BigInt::from(0)), Err(ModError::ZeroModulus));
+
+        // The minimum of a signed type is reduced before it is inverted.
+        assert_eq!(i64::MIN.try_mod_inv(&3), Ok(1));
+    }
+
+    // A type outside the crate that only implements mod_inv.
+    #[derive(Clone)]
+    struct Residue(u32);
+
+    impl From<Residue> for u32 {
+        fn from(x: Residue) -> u32 {
+            x.0
+        }
+    }
+
+    impl ModInv<u32> for Residue {
+        fn mod_inv(&self, modulus: &u32) -> Option<u32> {
+            self.0.mod_inv(modulus)
+        }
+    }
+
+    #[test]
+    fn test_try_mod_inv_default() {
+        assert_eq!(Residue(17).try_mod_inv(&29), Ok(12));
+        assert_eq!(Residue(12).try_mod_inv(&30), Err(ModError::NotInvertible { gcd: 6 }));
+        assert_eq!(Residue(12).try_mod_inv(&0), Err(ModError::ZeroModulus));
+    }
+
     #[test]
     fn test_non_invertible_bigint() {
         run_non_inv_tests(&non_invertible_bigint_test_cases());
@@ -270,8 +485,8 @@ mod bench {
         Test {
             data: vec! [
                 TestCase {
-                    value: value,
-                    modulus: modulus,
+                    value,
+                    modulus,
                 }
             ]
         }
@@ -1,4 +1,6 @@
-use num::{Zero, One, BigInt, Integer, PrimInt};
+use num::{Zero, BigInt, BigUint, Integer, PrimInt};
+use error::{ModError, nonzero_modulus};
+use wide::{mul_mod_u128, mul_mod_i128};
 
 
 /// The `ModMult` trait defines an interface for calculating the product of two integers 
@@ -10,73 +12,144 @@ pub trait ModMult {
     /// ```
     /// where x, y, and m are integers, and m is the modulus.
     ///
+    /// The result lies in ```[0, m)``` for a positive modulus, and in
+    /// ```(m, 0]``` for a negative one, for signed and unsigned types alike.
+    ///
+    /// For `BigInt` and `BigUint` every call multiplies and divides by m, and
+    /// keeps nothing between calls. Code that multiplies many times modulo the
+    /// same odd modulus should keep its operands in `MontgomeryForm` instead,
+    /// so that each product costs one Montgomery reduction and no division;
+    /// `SharedMod` does the same with Barrett reduction for even moduli.
+    ///
     /// # Panics
     /// when the modulus is zero.
-    fn mod_mult(self: &Self, other: &Self, modulus: &Self) -> Self;
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate num;
+    /// extern crate modal;
+    ///
+    /// use num::BigUint;
+    /// use modal::{ModMult, MontgomeryContext, MontgomeryForm};
+    ///
+    /// fn main() {
+    ///     let modulus = BigUint::from(1000003u32);
+    ///     let x       = BigUint::from(123456u32);
+    ///
+    ///     // One division per product.
+    ///     let mut acc = BigUint::from(1u32);
+    ///     for _ in 0..10 {
+    ///         acc = acc.mod_mult(&x, &modulus);
+    ///     }
+    ///
+    ///     // One context, and one Montgomery reduction per product.
+    ///     let ctx = MontgomeryContext::new(&modulus).unwrap();
+    ///     let x_mont = MontgomeryForm::new(&x, &ctx);
+    ///     let mut chain = MontgomeryForm::one(&ctx);
+    ///     for _ in 0..10 {
+    ///         chain = &chain * &x_mont;
+    ///     }
+    ///
+    ///     assert_eq!(chain.residue(), acc);
+    /// }
+    /// ```
+    fn mod_mult(&self, other: &Self, modulus: &Self) -> Self;
+
+    /// Computes ```x * y (mod m)``` like `mod_mult`, returning an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// `ModError::ZeroModulus` when the modulus is zero.
+    fn checked_mod_mult(&self, other: &Self, modulus: &Self) -> Result<Self, ModError<Self>>
+        where Self: Zero + Sized
+    {
+        nonzero_modulus(modulus).map(|_| self.mod_mult(other, modulus))
+    }
 }
 
+// Computes the product in the double width type W so that x * y cannot
+// overflow for any inputs of type T. The remainder is floored like the BigInt
+// implementation, so signed types also return the canonical residue.
 #[inline]
-fn __mod_mult<T: PrimInt>(x: &T, y: &T, modulus: &T) -> T {
+fn __mod_mult<T: PrimInt, W: PrimInt + Integer>(x: &T, y: &T, modulus: &T) -> T {
     assert!(*modulus != <T as Zero>::zero());
 
-    ((*x) * (*y)) % *modulus
-}
+    let wide_x: W = W::from(*x).unwrap();
+    let wide_y: W = W::from(*y).unwrap();
+    let wide_modulus: W = W::from(*modulus).unwrap();
 
-#[inline]
-fn montgomery_multiply(num: &BigInt, other_num: &BigInt, modulus: &BigInt) -> BigInt {
-    let base = BigInt::from(1 << 8);
-    // The base and modulus MUST be coprime for montgomery multiplication to work.
-    assert!(*modulus != <BigInt as Zero>::zero());
-    assert!(base.gcd(modulus) == <BigInt as One>::one());
-    // TODO: Replace this stub with the actual Montgomery multiplication algorithm.
-    (num * other_num).mod_floor(modulus)
+    T::from((wide_x * wide_y).mod_floor(&wide_modulus)).unwrap()
 }
 
-#[inline]
-fn default_multiply(num: &BigInt, other_num: &BigInt, modulus: &BigInt) -> BigInt {
-    assert!(*modulus != <BigInt as Zero>::zero());
+// A single product is cheapest with one multiplication and one division.
+// Montgomery and Barrett reduction only pay off once their setup and the
+// conversions into their representations are shared by a chain of products,
+// which `MontgomeryForm`, `SharedMod`, and `mod_exp` do.
+impl ModMult for BigInt {
+    fn mod_mult(&self, other: &BigInt, modulus: &BigInt) -> BigInt {
+        assert!(!modulus.is_zero());
 
-    (num * other_num).mod_floor(modulus)
+        (self * other).mod_floor(modulus)
+    }
 }
 
-impl ModMult for BigInt {
-    fn mod_mult(self: &BigInt, other: &BigInt, modulus: &BigInt) -> BigInt {
-        if modulus.is_odd() {
-            montgomery_multiply(self, other, modulus)
-        } else {
-            default_multiply(self, other, modulus)
-        }
+impl ModMult for BigUint {
+    fn mod_mult(&self, other: &BigUint, modulus: &BigUint) -> BigUint {
+        assert!(!modulus.is_zero());
+
+        (self * other).mod_floor(modulus)
     }
 }
 
 // Macro for generating ModMult implementations.
 macro_rules! mod_mult {
-    ( $ T : ty ) => {
+    ( $ T : ty, $ W : ty ) => {
         impl ModMult for $T {
-            fn mod_mult(self: &$T, other: &$T, modulus: &$T) -> $T {
-                __mod_mult(self, other, modulus)
+            fn mod_mult(&self, other: &$T, modulus: &$T) -> $T {
+                __mod_mult::<$T, $W>(self, other, modulus)
             }
         } 
     }
 }
 
 // Implementations of ModMult trait. 
-mod_mult!(u8);
-mod_mult!(u16);
-mod_mult!(u32);
-mod_mult!(u64);
-mod_mult!(usize);
-mod_mult!(i8);
-mod_mult!(i16);
-mod_mult!(i32);
-mod_mult!(i64);
-mod_mult!(isize);
+mod_mult!(u8, u16);
+mod_mult!(u16, u32);
+mod_mult!(u32, u64);
+mod_mult!(u64, u128);
+mod_mult!(usize, u128);
+mod_mult!(i8, i16);
+mod_mult!(i16, i32);
+mod_mult!(i32, i64);
+mod_mult!(i64, i128);
+mod_mult!(isize, i128);
+
+// There is no wider primitive to hold the product of two 128 bit values, so
+// these use a 256 bit product split across two words.
+impl ModMult for u128 {
+    fn mod_mult(&self, other: &u128, modulus: &u128) -> u128 {
+        assert!(*modulus != 0);
+
+        mul_mod_u128(*self, *other, *modulus)
+    }
+}
+
+impl ModMult for i128 {
+    fn mod_mult(&self, other: &i128, modulus: &i128) -> i128 {
+        assert!(*modulus != 0);
+
+        mul_mod_i128(*self, *other, *modulus)
+    }
+}
 
 
 #[cfg(test)]
 mod tests {
-    use num::BigInt;
+    use testing::check_signed_matches_bigint;
+    use num::{BigInt, Num};
     use modmult::ModMult;
+    use error::ModError;
 
 
     struct TestCase {
@@ -116,6 +189,14 @@ mod tests {
                     y: BigInt::from(446253906),
                     modulus: BigInt::from(53235608),
                     product: BigInt::from(14862186),
+                },
+                TestCase {
+                    x: <BigInt as Num>::from_str_radix("-29424885190633566625547596530635633692994051181214434796327203075", 10).unwrap(),
+                    y: <BigInt as Num>::from_str_radix("919087970205406919189208074679995123273961", 10).unwrap(),
+                    modulus: <BigInt as Num>::from_str_radix("11579208923731619542357098500868790785326998466564056403945758400790\
+                                                              8834671663", 10).unwrap(),
+                    product: <BigInt as Num>::from_str_radix("3904711834066951446492594860675642028207373529826520872617879826737\
+                                                              850204049", 10).unwrap(),
                 }
             ]
         }
@@ -134,6 +215,61 @@ mod tests {
         run_tests(&mod_mult_test_cases());
     }
 
+    #[test]
+    fn test_mod_mult_u64_no_overflow() {
+        let x: u64 = 0xfffffffffffffffe;
+        let y: u64 = 0xfffffffffffffffd;
+        let modulus: u64 = 0xffffffffffffffff;
+
+        assert_eq!(x.mod_mult(&y, &modulus), 2);
+        assert_eq!(4294967311u64.mod_mult(&4294967357, &18446744073709551557), 326417515470);
+        assert_eq!(200u8.mod_mult(&201, &251), 40);
+        assert_eq!(0x7fffffffusize.mod_mult(&0x7fffffff, &usize::MAX), 0x3fffffff00000001);
+    }
+
+    #[test]
+    fn test_mod_mult_signed_matches_bigint() {
+        check_signed_matches_bigint(|x, y, modulus| x.mod_mult(&y, &modulus), |x, y, modulus| x.mod_mult(y, modulus));
+    }
+
+    // BigInt has no conversions from the 128 bit types, so go through strings.
+    fn big<T: ToString>(x: T) -> BigInt {
+        x.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn test_mod_mult_128_matches_bigint() {
+        let unsigned: [u128; 7] = [0, 1, 0xfffffffffffffffd, 1 << 64, (1 << 127) - 1, u128::MAX - 1, u128::MAX];
+        let signed: [i128; 7] = [i128::MIN, -(1 << 100) - 3, -1, 0, 1, 0xfffffffffffffffd, i128::MAX];
+
+        for &x in unsigned.iter() {
+            for &y in unsigned.iter() {
+                for &modulus in unsigned.iter().skip(1) {
+                    let expected = big(x).mod_mult(&big(y), &big(modulus));
+
+                    assert_eq!(big(x.mod_mult(&y, &modulus)), expected);
+                }
+            }
+        }
+
+        for &x in signed.iter() {
+            for &y in signed.iter() {
+                for &modulus in signed.iter().filter(|&&m| m != 0) {
+                    let expected = big(x).mod_mult(&big(y), &big(modulus));
+
+                    assert_eq!(big(x.mod_mult(&y, &modulus)), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_checked_mod_mult() {
+        assert_eq!(3u64.checked_mod_mult(&5, &7), Ok(1));
+        assert_eq!(i64::MIN.checked_mod_mult(&i64::MIN, &0), Err(ModError::ZeroModulus));
+        assert_eq!(BigInt::from(3).checked_mod_mult(&BigInt::from(5), &BigInt::from(0)), Err(ModError::ZeroModulus));
+    }
+
     #[test]
     #[should_panic]
     fn test_mod_mult_zero_mod() {
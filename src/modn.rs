@@ -0,0 +1,645 @@
+use error::ModError;
+use num::traits;
+use std::ops::{Add, Sub, Mul, Div, Rem, Neg};
+use std::ops::{AddAssign, SubAssign, MulAssign, DivAssign, RemAssign};
+use std::iter::{Sum, Product};
+use std::fmt;
+use wide::{mul_wide_u32, mul_wide_u64, mul_wide_u128};
+
+
+// Macro for generating a residue type whose modulus is a const generic
+// parameter. Odd moduli keep values in Montgomery form with the radix
+// R == 2^bits; even moduli keep the residue itself, and reduce the double
+// width product by Barrett reduction.
+macro_rules! modn {
+    ( $ ( # [ $ attr : meta ] ) * $ Name : ident, $ T : ty, $ mul_wide : ident, $ steps : expr ) => {
+        /// A residue class of the integers modulo the compile time modulus `M`.
+        ///
+        /// The modulus is part of the type, so arithmetic between residues with
+        /// different moduli is a type error rather than a runtime panic, and a
+        /// value stores nothing but its residue. The reduction constants are
+        /// computed at compile time. Residues modulo an odd `M` are stored in
+        /// Montgomery form, and residues modulo an even `M` are reduced by
+        /// Barrett reduction, so that multiplication needs no division.
+        ///
+        /// # Panics
+        /// Division by a residue that has no inverse panics, and so does the
+        /// remainder operator, which exists for the `num::Num` bound. A zero
+        /// modulus is rejected at compile time.
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $Name<const M: $T> {
+            repr: $T,
+        }
+
+        impl<const M: $T> $Name<M> {
+            /// The modulus of the residue type.
+            pub const MODULUS: $T = M;
+
+            // Whether values are kept in Montgomery form.
+            const MONTGOMERY: bool = M & 1 == 1;
+
+            // -m ^ -1 (mod R) by Newton iteration. Each step doubles the number
+            // of correct low bits, starting from three.
+            const M_PRIME: $T = {
+                let mut inv: $T = M;
+                let mut i = 0;
+                while i < $steps {
+                    inv = inv.wrapping_mul((2 as $T).wrapping_sub(M.wrapping_mul(inv)));
+                    i += 1;
+                }
+
+                inv.wrapping_neg()
+            };
+
+            // R ^ 2 (mod m), which maps residues into Montgomery form. Evaluating
+            // it also checks that the modulus is nonzero.
+            const R2: $T = {
+                assert!(M != 0, "the modulus must be nonzero");
+
+                let mut r2 = (<$T>::MAX % M + 1) % M;
+                let mut i = 0;
+                while i < <$T>::BITS {
+                    r2 = Self::add_mod(r2, r2);
+                    i += 1;
+                }
+
+                r2
+            };
+
+            // floor((R ^ 2 - 1) / m) as a (high, low) pair, the Barrett factor
+            // for even moduli. The numerator is all ones, so long division
+            // shifts in a one bit at every step.
+            const MU: ($T, $T) = {
+                let mut rem: $T = 0;
+                let mut hi: $T = 0;
+                let mut lo: $T = 0;
+                let mut i = 0;
+                while i < 2 * <$T>::BITS {
+                    let (doubled, carry) = rem.overflowing_add(rem);
+                    let doubled = doubled | 1;
+                    let bit = carry || doubled >= M;
+
+                    rem = if bit { doubled.wrapping_sub(M) } else { doubled };
+                    hi = (hi << 1) | (lo >> (<$T>::BITS - 1));
+                    lo = (lo << 1) | (bit as $T);
+                    i += 1;
+                }
+
+                (hi, lo)
+            };
+
+            // Computes x + y (mod m) for residues x and y without overflowing.
+            #[inline]
+            const fn add_mod(x: $T, y: $T) -> $T {
+                let (sum, carry) = x.overflowing_add(y);
+
+                if carry || sum >= M {
+                    sum.wrapping_sub(M)
+                } else {
+                    sum
+                }
+            }
+
+            // Montgomery reduction of hi * R + lo, for hi < m.
+            #[inline]
+            const fn redc(hi: $T, lo: $T) -> $T {
+                let u = lo.wrapping_mul(Self::M_PRIME);
+                let (um_hi, um_lo) = $mul_wide(u, M);
+                // The low words sum to zero mod R; only their carry survives.
+                let (_, carry_lo) = lo.overflowing_add(um_lo);
+                let (t, carry_hi) = hi.overflowing_add(um_hi);
+                let (t, carry) = t.overflowing_add(carry_lo as $T);
+
+                if carry_hi || carry || t >= M {
+                    t.wrapping_sub(M)
+                } else {
+                    t
+                }
+            }
+
+            // Barrett reduction of x == hi * R + lo, for x < m ^ 2. The quotient
+            // estimate is the high half of x * mu / R ^ 2, which falls short of
+            // floor(x / m) by at most one, so the remainder is below 2m and one
+            // conditional subtraction finishes it.
+            #[inline]
+            const fn barrett(hi: $T, lo: $T) -> $T {
+                let (mu_hi, mu_lo) = Self::MU;
+                let (ll, _) = $mul_wide(lo, mu_lo);
+                let (lh_hi, lh_lo) = $mul_wide(lo, mu_hi);
+                let (hl_hi, hl_lo) = $mul_wide(hi, mu_lo);
+                let (_, hh_lo) = $mul_wide(hi, mu_hi);
+
+                // Carries out of the second word of the product.
+                let (middle, carry_a) = ll.overflowing_add(lh_lo);
+                let (_, carry_b) = middle.overflowing_add(hl_lo);
+                // The quotient is below m, so it fits in the third word alone.
+                let q = lh_hi
+                    .wrapping_add(hl_hi)
+                    .wrapping_add(hh_lo)
+                    .wrapping_add(carry_a as $T)
+                    .wrapping_add(carry_b as $T);
+
+                let (qm_hi, qm_lo) = $mul_wide(q, M);
+                let (r, borrow) = lo.overflowing_sub(qm_lo);
+                let r_hi = hi.wrapping_sub(qm_hi).wrapping_sub(borrow as $T);
+
+                if r_hi != 0 || r >= M {
+                    r.wrapping_sub(M)
+                } else {
+                    r
+                }
+            }
+
+            // Multiplies two stored representations.
+            #[inline]
+            const fn mul_repr(x: $T, y: $T) -> $T {
+                let (hi, lo) = $mul_wide(x, y);
+
+                if Self::MONTGOMERY {
+                    Self::redc(hi, lo)
+                } else {
+                    Self::barrett(hi, lo)
+                }
+            }
+
+            /// Constructs the residue class of `value` modulo `M`.
+            #[inline]
+            pub const fn new(value: $T) -> $Name<M> {
+                let r2 = Self::R2;
+                let value = value % M;
+
+                if Self::MONTGOMERY {
+                    $Name { repr: Self::mul_repr(value, r2) }
+                } else {
+                    $Name { repr: value }
+                }
+            }
+
+            /// Returns the canonical residue in ```[0, M)```.
+            #[inline]
+            pub const fn value(self) -> $T {
+                if Self::MONTGOMERY {
+                    Self::redc(0, self.repr)
+                } else {
+                    self.repr
+                }
+            }
+
+            /// Returns the modulus of the residue type.
+            #[inline]
+            pub const fn modulus() -> $T {
+                M
+            }
+
+            /// Returns the residue class of zero.
+            #[inline]
+            pub const fn zero() -> $Name<M> {
+                $Name { repr: 0 }
+            }
+
+            /// Returns the residue class of one.
+            #[inline]
+            pub const fn one() -> $Name<M> {
+                $Name::new(1)
+            }
+
+            /// Computes ```self ^ exponent``` by square and multiply.
+            pub fn pow(self, exponent: u64) -> $Name<M> {
+                let mut result = $Name::one();
+                let mut base = self;
+                let mut divided_exponent = exponent;
+
+                while divided_exponent != 0 {
+                    if divided_exponent & 1 == 1 {
+                        result *= base;
+                    }
+                    divided_exponent >>= 1;
+                    base *= base;
+                }
+
+                result
+            }
+
+            /// Computes the multiplicative inverse of the residue class.
+            ///
+            /// # Errors
+            /// Returns ```None``` when the value is not invertible modulo `M`.
+            pub fn inv(self) -> Option<$Name<M>> {
+                self.checked_inv().ok()
+            }
+
+            /// Computes the multiplicative inverse of the residue class,
+            /// returning an error that carries the common factor when there is
+            /// none.
+            pub fn checked_inv(self) -> Result<$Name<M>, ModError<$T>> {
+                // Extended Euclid, with the coefficient of the value tracked as
+                // a residue so it never goes negative.
+                let mut r0 = M;
+                let mut r1 = self.value();
+                let mut t0 = $Name::zero();
+                let mut t1 = $Name::one();
+
+                while r1 != 0 {
+                    let q = r0 / r1;
+                    let r2 = r0 - q * r1;
+                    let t2 = t0 - $Name::new(q) * t1;
+
+                    r0 = r1;
+                    r1 = r2;
+                    t0 = t1;
+                    t1 = t2;
+                }
+
+                if r0 == 1 {
+                    Ok(t0)
+                } else {
+                    Err(ModError::NotInvertible { gcd: r0 })
+                }
+            }
+
+            /// Computes ```self / rhs == self * rhs ^ -1```, returning an error
+            /// instead of panicking when rhs is not invertible.
+            pub fn checked_div(self, rhs: $Name<M>) -> Result<$Name<M>, ModError<$T>> {
+                rhs.checked_inv().map(|rhs_inv| self * rhs_inv)
+            }
+        }
+
+        impl<const M: $T> fmt::Debug for $Name<M> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_struct(stringify!($Name))
+                    .field("value", &self.value())
+                    .field("modulus", &M)
+                    .finish()
+            }
+        }
+
+        impl<const M: $T> Default for $Name<M> {
+            fn default() -> $Name<M> {
+                $Name::zero()
+            }
+        }
+
+        impl<const M: $T> From<$T> for $Name<M> {
+            fn from(value: $T) -> $Name<M> {
+                $Name::new(value)
+            }
+        }
+
+        impl<const M: $T> AddAssign for $Name<M> {
+            #[inline]
+            fn add_assign(&mut self, rhs: $Name<M>) {
+                self.repr = Self::add_mod(self.repr, rhs.repr);
+            }
+        }
+
+        impl<const M: $T> SubAssign for $Name<M> {
+            #[inline]
+            fn sub_assign(&mut self, rhs: $Name<M>) {
+                self.repr = if self.repr >= rhs.repr {
+                    self.repr - rhs.repr
+                } else {
+                    self.repr.wrapping_sub(rhs.repr).wrapping_add(M)
+                };
+            }
+        }
+
+        impl<const M: $T> MulAssign for $Name<M> {
+            #[inline]
+            fn mul_assign(&mut self, rhs: $Name<M>) {
+                self.repr = Self::mul_repr(self.repr, rhs.repr);
+            }
+        }
+
+        impl<const M: $T> DivAssign for $Name<M> {
+            fn div_assign(&mut self, rhs: $Name<M>) {
+                *self = self.checked_div(rhs).unwrap_or_else(|err| panic!("{}", err));
+            }
+        }
+
+        // The remainder operator is only here because `num::Num` requires it.
+        impl<const M: $T> RemAssign for $Name<M> {
+            /// Every invertible residue divides every residue exactly, so the
+            /// remainder is always zero.
+            ///
+            /// # Panics
+            /// Panics when rhs is not invertible, like division.
+            fn rem_assign(&mut self, rhs: $Name<M>) {
+                rhs.checked_inv().unwrap_or_else(|err| panic!("{}", err));
+                *self = $Name::zero();
+            }
+        }
+
+        impl<const M: $T> Neg for $Name<M> {
+            type Output = $Name<M>;
+
+            #[inline]
+            fn neg(self) -> $Name<M> {
+                $Name::zero() - self
+            }
+        }
+
+        modn_binop!($Name, $T, impl Add, add, add_assign);
+        modn_binop!($Name, $T, impl Sub, sub, sub_assign);
+        modn_binop!($Name, $T, impl Mul, mul, mul_assign);
+        modn_binop!($Name, $T, impl Div, div, div_assign);
+        modn_binop!($Name, $T, impl Rem, rem, rem_assign);
+
+        impl<const M: $T> traits::Zero for $Name<M> {
+            fn zero() -> $Name<M> {
+                $Name::zero()
+            }
+
+            fn is_zero(&self) -> bool {
+                self.repr == 0
+            }
+        }
+
+        impl<const M: $T> traits::One for $Name<M> {
+            fn one() -> $Name<M> {
+                $Name::one()
+            }
+        }
+
+        impl<const M: $T> traits::Num for $Name<M> {
+            type FromStrRadixErr = <$T as traits::Num>::FromStrRadixErr;
+
+            fn from_str_radix(s: &str, radix: u32) -> Result<$Name<M>, Self::FromStrRadixErr> {
+                <$T as traits::Num>::from_str_radix(s, radix).map($Name::new)
+            }
+        }
+
+        impl<const M: $T> traits::Pow<u64> for $Name<M> {
+            type Output = $Name<M>;
+
+            fn pow(self, exponent: u64) -> $Name<M> {
+                $Name::pow(self, exponent)
+            }
+        }
+
+        impl<const M: $T> traits::Inv for $Name<M> {
+            type Output = $Name<M>;
+
+            fn inv(self) -> $Name<M> {
+                self.checked_inv().unwrap_or_else(|err| panic!("{}", err))
+            }
+        }
+
+        impl<const M: $T> traits::CheckedDiv for $Name<M> {
+            fn checked_div(&self, rhs: &$Name<M>) -> Option<$Name<M>> {
+                $Name::checked_div(*self, *rhs).ok()
+            }
+        }
+
+        impl<const M: $T> Sum for $Name<M> {
+            fn sum<It: Iterator<Item=$Name<M>>>(iter: It) -> $Name<M> {
+                iter.fold($Name::zero(), |acc, x| acc + x)
+            }
+        }
+
+        impl<'a, const M: $T> Sum<&'a $Name<M>> for $Name<M> {
+            fn sum<It: Iterator<Item=&'a $Name<M>>>(iter: It) -> $Name<M> {
+                iter.fold($Name::zero(), |acc, x| acc + *x)
+            }
+        }
+
+        impl<const M: $T> Product for $Name<M> {
+            fn product<It: Iterator<Item=$Name<M>>>(iter: It) -> $Name<M> {
+                iter.fold($Name::one(), |acc, x| acc * x)
+            }
+        }
+
+        impl<'a, const M: $T> Product<&'a $Name<M>> for $Name<M> {
+            fn product<It: Iterator<Item=&'a $Name<M>>>(iter: It) -> $Name<M> {
+                iter.fold($Name::one(), |acc, x| acc * *x)
+            }
+        }
+    }
+}
+
+// Macro for generating a binary operator on a fixed modulus residue type, for
+// owned and borrowed right hand sides, from its compound assignment operator.
+macro_rules! modn_binop {
+    ( $ Name : ident, $ T : ty, impl $ Op : ident, $ op : ident, $ op_assign : ident ) => {
+        impl<const M: $T> $Op for $Name<M> {
+            type Output = $Name<M>;
+
+            #[inline]
+            fn $op(mut self, rhs: $Name<M>) -> $Name<M> {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl<'a, const M: $T> $Op<&'a $Name<M>> for $Name<M> {
+            type Output = $Name<M>;
+
+            #[inline]
+            fn $op(mut self, rhs: &'a $Name<M>) -> $Name<M> {
+                self.$op_assign(*rhs);
+                self
+            }
+        }
+    }
+}
+
+modn!(ModN32, u32, mul_wide_u32, 4);
+modn!(
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// extern crate modal;
+    ///
+    /// use modal::ModN;
+    ///
+    /// fn main() {
+    ///     type F = ModN<998244353>;
+    ///
+    ///     let x = F::new(3);
+    ///
+    ///     assert_eq!((x * F::new(5)).value(), 15);
+    ///     assert_eq!(x.pow(998244352), F::one());
+    ///     assert_eq!((F::one() / x * x).value(), 1);
+    /// }
+    /// ```
+    ///
+    /// Mixing moduli does not compile.
+    ///
+    /// ```compile_fail
+    /// extern crate modal;
+    ///
+    /// use modal::ModN;
+    ///
+    /// fn main() {
+    ///     let _ = ModN::<7>::new(3) + ModN::<11>::new(3);
+    /// }
+    /// ```
+    ModN, u64, mul_wide_u64, 5
+);
+modn!(ModN128, u128, mul_wide_u128, 6);
+
+
+#[cfg(test)]
+mod tests {
+    use super::{ModN, ModN32, ModN128};
+    use error::ModError;
+    use num;
+    use num::traits::{self, Num};
+
+
+    const P: u64 = 998244353;
+
+    #[test]
+    fn test_modn_arithmetic_odd_modulus() {
+        let x = ModN::<P>::new(123456789123);
+        let y = ModN::<P>::new(987654321);
+
+        assert_eq!(x.value(), 123456789123 % P);
+        assert_eq!((x + y).value(), (123456789123 % P + 987654321) % P);
+        assert_eq!((x - y).value(), (123456789123 % P + P - 987654321) % P);
+        assert_eq!((x * y).value(), ((123456789123 % P) * 987654321) % P);
+        assert_eq!((-y).value(), P - 987654321);
+        assert_eq!((x / y * y), x);
+        assert_eq!(ModN::<P>::new(3).pow(P - 1), ModN::one());
+        assert_eq!(ModN::<P>::modulus(), P);
+    }
+
+    #[test]
+    fn test_modn_arithmetic_even_modulus() {
+        type R = ModN<{ 1 << 40 }>;
+
+        let x = R::new(u64::MAX);
+        let y = R::new(0xdeadbeef);
+
+        assert_eq!(x.value(), (1 << 40) - 1);
+        assert_eq!((x * y).value(), (1u64 << 40) - 0xdeadbeef);
+        assert_eq!((x + y).value(), 0xdeadbeef - 1);
+        assert_eq!(y.inv().unwrap() * y, R::one());
+        assert_eq!(R::new(6).checked_inv(), Err(ModError::NotInvertible { gcd: 2 }));
+    }
+
+    #[test]
+    fn test_modn_large_moduli() {
+        const Q: u64 = 0xffffffffffffffc5;
+        let x = ModN::<Q>::new(0xfffffffffffffffe);
+
+        assert_eq!(x.value(), 0x39);
+        assert_eq!((x * x).value(), 0xcb1);
+        assert_eq!((x + ModN::new(Q - 1)).value(), 0x38);
+
+        let z = ModN32::<0xfffffffb>::new(0xfffffffa);
+
+        assert_eq!((z * z).value(), 1);
+        assert_eq!(z.inv(), Some(z));
+    }
+
+    // Checks products modulo an even M against the double width primitive,
+    // over values spread across the whole range.
+    fn check_even_products<const M: u64>() {
+        let mut x: u64 = 0x9e3779b97f4a7c15;
+
+        for _ in 0..1000 {
+            let y = x.rotate_left(29) ^ 0xbf58476d1ce4e5b9;
+            let expected = ((x as u128) * (y as u128) % (M as u128)) as u64;
+
+            assert_eq!((ModN::<M>::new(x) * ModN::new(y)).value(), expected);
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        }
+
+        let top = ModN::<M>::new(M - 1);
+
+        assert_eq!((top * top).value(), 1 % M);
+    }
+
+    #[test]
+    fn test_modn_barrett_reduction() {
+        check_even_products::<2>();
+        check_even_products::<{ 1 << 63 }>();
+        check_even_products::<{ u64::MAX - 1 }>();
+        check_even_products::<{ (1 << 63) + 2 }>();
+        check_even_products::<0xdeadbeefcafe>();
+        check_even_products::<{ 3 << 40 }>();
+
+        type E = ModN32<{ u32::MAX - 1 }>;
+
+        assert_eq!((E::new(0x89abcdef) * E::new(0xfedcba98)).value(),
+                   ((0x89abcdefu64 * 0xfedcba98) % (u32::MAX as u64 - 1)) as u32);
+
+        const EVEN: u128 = (1 << 127) + 2;
+        let z = ModN128::<EVEN>::new(1 << 126);
+
+        // 2 ^ 127 == -2, so (2 ^ 126) ^ 2 == 2 ^ 125 * 2 ^ 127 == -2 ^ 126.
+        assert_eq!((z * z).value(), EVEN - (1 << 126));
+    }
+
+    #[test]
+    fn test_modn_u128() {
+        const MERSENNE: u128 = (1 << 127) - 1;
+        let x = ModN128::<MERSENNE>::new(1 << 100);
+
+        // 2 ^ 127 == 1, so (2 ^ 100) ^ 2 == 2 ^ 73.
+        assert_eq!((x * x).value(), 1 << 73);
+        assert_eq!(x.pow(127), ModN128::one());
+        assert_eq!((x / x), ModN128::one());
+
+        const EVEN: u128 = u128::MAX - 1;
+        let y = ModN128::<EVEN>::new(u128::MAX - 2);
+
+        assert_eq!((y * y).value(), 1);
+        assert_eq!((y + y).value(), EVEN - 2);
+    }
+
+    #[test]
+    fn test_modn_identities() {
+        let values: Vec<ModN<P>> = (1..11).map(ModN::new).collect();
+
+        assert_eq!(values.iter().sum::<ModN<P>>().value(), 55);
+        assert_eq!(values.iter().product::<ModN<P>>().value(), 3628800);
+        assert_eq!(values.into_iter().sum::<ModN<P>>(), ModN::new(55));
+        assert_eq!(ModN::<1>::new(5), ModN::<1>::one());
+        assert_eq!(ModN::<P>::default(), ModN::zero());
+        assert_eq!(format!("{:?}", ModN::<7>::new(12)), "ModN { value: 5, modulus: 7 }");
+    }
+
+    // Evaluates a polynomial by Horner's rule, written only against the num
+    // traits.
+    fn horner<T: Num + Copy>(coefficients: &[T], x: T) -> T {
+        coefficients.iter().rev().fold(T::zero(), |acc, &c| acc * x + c)
+    }
+
+    #[test]
+    fn test_modn_num_traits() {
+        type F = ModN<P>;
+
+        let coefficients: Vec<F> = [1, 2, 3].iter().map(|&c| F::new(c)).collect();
+
+        // 1 + 2x + 3x^2 at x == P - 1.
+        assert_eq!(horner(&coefficients, F::new(P - 1)), F::new(2));
+        assert_eq!(num::pow(F::new(2), 10), F::new(1024));
+        assert_eq!(F::from_str_radix("ff", 16), Ok(F::new(255)));
+        assert_eq!(traits::Inv::inv(F::new(2)) * F::new(2), F::one());
+        assert_eq!(traits::Pow::pow(F::new(3), P - 1), F::one());
+        assert_eq!(F::new(10) % F::new(3), F::zero());
+        assert!(traits::Zero::is_zero(&F::new(P)));
+        assert!(traits::One::is_one(&F::new(P + 1)));
+
+        type E = ModN32<12>;
+
+        assert_eq!(traits::CheckedDiv::checked_div(&E::new(3), &E::new(4)), None);
+        assert_eq!(traits::CheckedDiv::checked_div(&E::new(3), &E::new(5)), Some(E::new(3)));
+        assert_eq!(horner(&[ModN128::<{ u128::MAX }>::new(5)], ModN128::new(7)).value(), 5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_modn_rem_non_invertible() {
+        let _ = ModN32::<12>::new(3) % ModN32::new(4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_modn_div_non_invertible() {
+        let _ = ModN::<{ 1 << 40 }>::new(3) / ModN::new(4);
+    }
+}
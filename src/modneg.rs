@@ -0,0 +1,158 @@
+use num::{Zero, BigUint, BigInt, Integer, PrimInt};
+use modsub::ModSub;
+use error::{ModError, nonzero_modulus};
+
+
+/// The `ModNeg` trait defines an interface for calculating the additive inverse
+/// of an integer modulo a modulus.
+pub trait ModNeg {
+    /// The function `mod_neg` computes
+    /// ```text
+    /// -x (mod m)
+    /// ```
+    /// where x and m are integers, and m is the modulus. The result is the
+    /// same residue `mod_sub` returns for ```0 - x```.
+    ///
+    /// # Panics
+    /// when the modulus is zero.
+    fn mod_neg(&self, modulus: &Self) -> Self;
+
+    /// Computes ```-x (mod m)``` like `mod_neg`, returning an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// `ModError::ZeroModulus` when the modulus is zero.
+    fn checked_mod_neg(&self, modulus: &Self) -> Result<Self, ModError<Self>>
+        where Self: Zero + Sized
+    {
+        nonzero_modulus(modulus).map(|_| self.mod_neg(modulus))
+    }
+}
+
+#[inline]
+fn __mod_neg<T: PrimInt + ModSub>(x: &T, modulus: &T) -> T {
+    <T as Zero>::zero().mod_sub(x, modulus)
+}
+
+impl ModNeg for BigInt {
+    fn mod_neg(&self, modulus: &BigInt) -> BigInt {
+        assert!(!modulus.is_zero());
+
+        (-self).mod_floor(modulus)
+    }
+}
+
+impl ModNeg for BigUint {
+    fn mod_neg(&self, modulus: &BigUint) -> BigUint {
+        assert!(!modulus.is_zero());
+
+        let x = self.mod_floor(modulus);
+
+        if x.is_zero() {
+            x
+        } else {
+            modulus - x
+        }
+    }
+}
+
+// Macro for generating ModNeg implementations.
+macro_rules! mod_neg {
+    ( $ T : ty ) => {
+        impl ModNeg for $T {
+            fn mod_neg(&self, modulus: &$T) -> $T {
+                __mod_neg(self, modulus)
+            }
+        }
+    }
+}
+
+// Implementations of ModNeg trait.
+mod_neg!(u8);
+mod_neg!(u16);
+mod_neg!(u32);
+mod_neg!(u64);
+mod_neg!(usize);
+mod_neg!(u128);
+mod_neg!(i8);
+mod_neg!(i16);
+mod_neg!(i32);
+mod_neg!(i64);
+mod_neg!(isize);
+mod_neg!(i128);
+
+#[cfg(test)]
+mod tests {
+    use num::{BigInt, BigUint};
+    use super::ModNeg;
+    use error::ModError;
+
+
+    struct TestCase {
+        x: BigInt,
+        modulus: BigInt,
+        neg: BigInt,
+    }
+
+    struct Test {
+        data: Vec<TestCase>,
+    }
+
+    fn mod_neg_test_cases() -> Test {
+        Test {
+            data: vec! [
+                TestCase {
+                    x: BigInt::from(464283712),
+                    modulus: BigInt::from(635717262),
+                    neg: BigInt::from(171433550),
+                },
+                TestCase {
+                    x: BigInt::from(0),
+                    modulus: BigInt::from(538744916),
+                    neg: BigInt::from(0),
+                },
+                TestCase {
+                    x: BigInt::from(735172765),
+                    modulus: BigInt::from(53235608),
+                    neg: BigInt::from(10125747),
+                }
+            ]
+        }
+    }
+
+    fn run_tests(tests: &Test) {
+        for test_case in tests.data.iter() {
+            assert_eq!(test_case.x.mod_neg(&test_case.modulus), test_case.neg);
+
+            let x = test_case.x.to_biguint().unwrap();
+            let modulus = test_case.modulus.to_biguint().unwrap();
+
+            assert_eq!(x.mod_neg(&modulus), test_case.neg.to_biguint().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_mod_neg() {
+        run_tests(&mod_neg_test_cases());
+    }
+
+    #[test]
+    fn test_mod_neg_primitive() {
+        assert_eq!(3u64.mod_neg(&0xffffffffffffffff), 0xfffffffffffffffc);
+        assert_eq!(0u8.mod_neg(&251), 0);
+        assert_eq!((-3i32).mod_neg(&7), 3);
+    }
+
+    #[test]
+    fn test_checked_mod_neg() {
+        assert_eq!(3u64.checked_mod_neg(&7), Ok(4));
+        assert_eq!(3i16.checked_mod_neg(&0), Err(ModError::ZeroModulus));
+        assert_eq!(BigUint::from(3u32).checked_mod_neg(&BigUint::from(0u32)), Err(ModError::ZeroModulus));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_neg_zero_mod() {
+        BigUint::from(5u32).mod_neg(&BigUint::from(0u32));
+    }
+}
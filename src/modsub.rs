@@ -1,44 +1,84 @@
 use num::{Zero, BigUint, BigInt, Integer, PrimInt};
+use error::{ModError, nonzero_modulus};
 
 
 /// The `ModSub` trait defines an interface for calculating the difference of two integers
 /// modulo a modulus.
 pub trait ModSub {
-    /// The function `mod_sub` computes the sum
+    /// The function `mod_sub` computes the difference
     /// ```text
     /// x - y (mod m)
     /// ```
     /// where x, y, and m are integers, and m is the modulus.
     ///
+    /// Operands outside the range of residues are reduced modulo m first, so
+    /// every input is valid. The result lies in ```[0, m)``` for a positive
+    /// modulus, and in ```(m, 0]``` for a negative one.
+    ///
     /// # Panics
     /// when the modulus is zero.
-    fn mod_sub(self: &Self, other: &Self, modulus: &Self) -> Self;
+    fn mod_sub(&self, other: &Self, modulus: &Self) -> Self;
+
+    /// Computes ```x - y (mod m)``` like `mod_sub`, returning an error instead of
+    /// panicking.
+    ///
+    /// # Errors
+    /// `ModError::ZeroModulus` when the modulus is zero.
+    fn checked_mod_sub(&self, other: &Self, modulus: &Self) -> Result<Self, ModError<Self>>
+        where Self: Zero + Sized
+    {
+        nonzero_modulus(modulus).map(|_| self.mod_sub(other, modulus))
+    }
 }
 
 impl ModSub for BigInt {
-    fn mod_sub(self: &BigInt, other: &BigInt, modulus: &BigInt) -> BigInt {
+    fn mod_sub(&self, other: &BigInt, modulus: &BigInt) -> BigInt {
         (self - other).mod_floor(modulus)
     }
 }
 
 impl ModSub for BigUint {
-    fn mod_sub(self: &BigUint, other: &BigUint, modulus: &BigUint) -> BigUint {
-        (self - other).mod_floor(modulus)
+    fn mod_sub(&self, other: &BigUint, modulus: &BigUint) -> BigUint {
+        assert!(!modulus.is_zero());
+
+        let x = self.mod_floor(modulus);
+        let y = other.mod_floor(modulus);
+
+        // Subtracting in the other order when y > x avoids the underflow
+        // panic of BigUint subtraction.
+        if x >= y {
+            x - y
+        } else {
+            modulus - (y - x)
+        }
     }
 }
 
+// Subtracts two residues with a conditional addition instead of computing
+// x - y directly, so the difference never underflows T.
 #[inline]
-fn __mod_sub<T: PrimInt>(x: &T, y: &T, modulus: &T) -> T {
-    assert!(*modulus != <T as Zero>::zero());
+fn __mod_sub<T: PrimInt + Integer>(x: &T, y: &T, modulus: &T) -> T {
+    let zero = <T as Zero>::zero();
 
-    ((*x) - (*y)) % *modulus
+    assert!(*modulus != zero);
+
+    let x = x.mod_floor(modulus);
+    let y = y.mod_floor(modulus);
+    let wraps = if *modulus > zero { x < y } else { x > y };
+
+    if wraps {
+        // m - y cannot overflow since y lies between 0 and m.
+        x + (*modulus - y)
+    } else {
+        x - y
+    }
 }
 
 // Macro for generating ModSub implementations.
 macro_rules! mod_sub {
     ( $ T : ty ) => {
         impl ModSub for $T {
-            fn mod_sub(self: &$T, other: &$T, modulus: &$T) -> $T {
+            fn mod_sub(&self, other: &$T, modulus: &$T) -> $T {
                 __mod_sub(self, other, modulus)
             }
         }
@@ -51,16 +91,20 @@ mod_sub!(u16);
 mod_sub!(u32);
 mod_sub!(u64);
 mod_sub!(usize);
+mod_sub!(u128);
 mod_sub!(i8);
 mod_sub!(i16);
 mod_sub!(i32);
 mod_sub!(i64);
 mod_sub!(isize);
+mod_sub!(i128);
 
 #[cfg(test)]
 mod tests {
-    use num::BigInt;
+    use testing::check_signed_matches_bigint;
+    use num::{BigInt, BigUint, Integer};
     use super::ModSub;
+    use error::ModError;
 
 
     struct TestCase {
@@ -118,6 +162,40 @@ mod tests {
         run_tests(&mod_sub_test_cases());
     }
 
+    #[test]
+    fn test_mod_sub_no_underflow() {
+        assert_eq!(3u64.mod_sub(&0xfffffffffffffffd, &0xffffffffffffffff), 5);
+        assert_eq!(0u8.mod_sub(&1, &251), 250);
+        assert_eq!(255u8.mod_sub(&3, &251), 1);
+        assert_eq!(i64::MIN.mod_sub(&i64::MAX, &i64::MAX), i64::MAX - 1);
+        assert_eq!(3i32.mod_sub(&4, &-5), -1);
+    }
+
+    #[test]
+    fn test_mod_sub_signed_matches_bigint() {
+        check_signed_matches_bigint(|x, y, modulus| x.mod_sub(&y, &modulus), |x, y, modulus| x.mod_sub(y, modulus));
+    }
+
+    #[test]
+    fn test_mod_sub_biguint() {
+        for test_case in mod_sub_test_cases().data.iter() {
+            let x = test_case.x.mod_floor(&test_case.modulus).to_biguint().unwrap();
+            let y = test_case.y.to_biguint().unwrap();
+            let modulus = test_case.modulus.to_biguint().unwrap();
+
+            assert_eq!(x.mod_sub(&y, &modulus), test_case.diff.to_biguint().unwrap());
+        }
+
+        assert_eq!(BigUint::from(3u32).mod_sub(&BigUint::from(1000u32), &BigUint::from(7u32)), BigUint::from(4u32));
+    }
+
+    #[test]
+    fn test_checked_mod_sub() {
+        assert_eq!(3u64.checked_mod_sub(&5, &7), Ok(5));
+        assert_eq!(3u8.checked_mod_sub(&5, &0), Err(ModError::ZeroModulus));
+        assert_eq!(BigUint::from(3u32).checked_mod_sub(&BigUint::from(5u32), &BigUint::from(0u32)), Err(ModError::ZeroModulus));
+    }
+
     #[test]
     #[should_panic]
     fn test_mod_sub_zero_mod() {
@@ -1,244 +1,965 @@
 use modinv::ModInv;
 use modmult::ModMult;
-use std::ops::{Add, Mul, Sub, Neg};
-use std::fmt::Debug;
-use num::{Zero, One, Integer, Bounded};
-
-
-#[derive(Clone, Debug)]
+use modadd::ModAdd;
+use modsub::ModSub;
+use modneg::ModNeg;
+use modexp::{ModExp, Exponent};
+use std::ops::{Add, Mul, Sub, Neg, Div};
+use std::ops::{AddAssign, SubAssign, MulAssign, DivAssign};
+use std::iter::{Sum, Product};
+use std::fmt::{self, Debug, Display};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use error::{ModError, ParseModError, nonzero_modulus};
+use num::{Zero, One, Integer, Num, BigUint};
+use num::traits;
+
+
+/// A residue class of the integers modulo a modulus m.
+///
+/// A `Mod<I>` stores a value reduced into the canonical range of residues,
+/// ```[0, m)``` for a positive modulus and ```(m, 0]``` for a negative one,
+/// together with its modulus. The arithmetic operators are implemented with
+/// the crate's `ModAdd`, `ModSub`, `ModMult`, `ModNeg`, and `ModInv` traits
+/// for every combination of owned and borrowed operands, along with the
+/// compound assignment operators. Operators taking ownership of the left
+/// operand update it in place rather than allocating a new residue.
+///
+/// # Panics
+/// Arithmetic between residues with different moduli panics, as does
+/// division by a residue that has no inverse. The `checked_*` methods are
+/// non-panicking alternatives that return a `ModError` instead.
+///
+/// Two residue classes are equal when both their values and their moduli
+/// agree. They display as ```5 (mod 7)```, which `FromStr` parses back, and
+/// are ordered by modulus first and value second.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigInt;
+/// use modal::Mod;
+///
+/// fn main() {
+///     let modulus = BigInt::from(29);
+///     let x = Mod::new(&BigInt::from(17), &modulus);
+///     let y = Mod::new(&BigInt::from(-8), &modulus);
+///
+///     assert_eq!(*(&x + &y).value(), BigInt::from(9));
+///     assert_eq!(*(&x * &y).value(), BigInt::from(9));
+///     assert_eq!(*(&x / &y).value(), BigInt::from(16));
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Mod<I> where I: Clone + Debug {
     value: I,
     modulus: I,
 }
 
+impl<I> Mod<I> where I: Clone + Debug {
+    /// Returns the canonical representative of the residue class.
+    pub fn value(&self) -> &I {
+        &self.value
+    }
+
+    /// Returns the modulus of the residue class.
+    pub fn modulus(&self) -> &I {
+        &self.modulus
+    }
+
+    /// Returns a copy of the canonical representative of the residue class.
+    pub fn un_mod(&self) -> I {
+        self.value.clone()
+    }
+}
+
 impl<I> Mod<I> where I: Debug + Clone + Eq + ModInv<I> + Integer {
-    fn new(value: &I, modulus: &I) -> Mod<I> {
+    /// Constructs the residue class of `value` modulo `modulus`. The value is
+    /// reduced into the canonical range first, which is ```[0, m)``` for a
+    /// positive modulus and ```(m, 0]``` for a negative one.
+    ///
+    /// # Panics
+    /// when the modulus is zero.
+    pub fn new(value: &I, modulus: &I) -> Mod<I> {
         Mod {
             value: value.mod_floor(modulus),
             modulus: modulus.clone(),
         }
     }
 
-    fn un_mod(&self) -> I {
-        self.value.clone()
+    /// Constructs the residue class of `value` modulo `modulus`, returning an
+    /// error instead of panicking when the modulus is zero.
+    pub fn try_new(value: &I, modulus: &I) -> Result<Mod<I>, ModError<I>> {
+        nonzero_modulus(modulus).map(|_| Mod::new(value, modulus))
     }
 
-    fn inv(&self) -> Option<Mod<I>> {
-        self.value.mod_inv(&self.modulus).map(|val| { 
-            Mod::new(&val, &self.modulus) 
+    /// Computes the multiplicative inverse of the residue class.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the value is not invertible modulo the modulus.
+    pub fn inv(&self) -> Option<Mod<I>> {
+        self.value.mod_inv(&self.modulus).map(|val| {
+            Mod::new(&val, &self.modulus)
         })
     }
 
-    fn has_inv(&self) -> bool {
+    /// Tests whether the residue class has a multiplicative inverse.
+    pub fn has_inv(&self) -> bool {
         self.inv().is_some()
     }
 
-    fn zero(modulus: &I) -> Mod<I> {
+    /// Returns the residue class of zero modulo `modulus`.
+    pub fn zero(modulus: &I) -> Mod<I> {
         Mod::new(&Zero::zero(), modulus)
     }
 
-    fn one(modulus: &I) -> Mod<I> {
+    /// Returns the residue class of one modulo `modulus`.
+    pub fn one(modulus: &I) -> Mod<I> {
         Mod::new(&One::one(), modulus)
     }
-}
 
-impl<I> Add<Mod<I>> for Mod<I> 
-    where I: Clone + Eq + Debug + Add<I, Output=I> + ModInv<I> + Integer 
-{
-    type Output = Mod<I>;
-
-    fn add(self, rhs: Mod<I>) -> Mod<I> {
-        assert_eq!(self.value, rhs.value);
+    /// Tests whether the residue class is the class of zero.
+    pub fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
 
-        let new_value = (self.value + rhs.value).mod_floor(&self.modulus);
-        
-        Mod::new(&new_value, &self.modulus)
+    /// Tests whether the residue class is the class of one. Modulo one, this
+    /// is the class of zero.
+    pub fn is_one(&self) -> bool {
+        self.value == I::one().mod_floor(&self.modulus)
     }
-}
 
-impl<'a, I> Add<&'a Mod<I>> for Mod<I>
-    where I: Clone + Eq + Debug + Add<&'a I, Output=I> + ModInv<I> + Integer
-{
-    type Output = Mod<I>;
+    /// Parses the residue class of a value written in the given radix, as
+    /// `Num::from_str_radix` does for the underlying integer type.
+    ///
+    /// # Panics
+    /// when the modulus is zero.
+    pub fn from_str_radix(s: &str, radix: u32, modulus: &I) -> Result<Mod<I>, I::FromStrRadixErr>
+        where I: Num
+    {
+        I::from_str_radix(s, radix).map(|value| Mod::new(&value, modulus))
+    }
 
-    fn add(self, rhs: &'a Mod<I>) -> Mod<I> {
-        assert_eq!(self.value, rhs.value);
+    /// Computes ```self / rhs == self * rhs ^ -1```, returning an error instead
+    /// of panicking when the moduli differ or rhs is not invertible.
+    pub fn checked_div(&self, rhs: &Mod<I>) -> Result<Mod<I>, ModError<I>> where I: ModMult {
+        self.check_modulus(rhs)?;
 
-        let new_value = (self.value + &rhs.value).mod_floor(&self.modulus);
+        let rhs_inv = rhs.checked_inv()?;
 
-        Mod::new(&new_value, &self.modulus)
+        self.checked_mul(&rhs_inv)
     }
-}
 
-impl<'a, I> Add<Mod<I>> for &'a Mod<I>
-    where I: Clone + Eq + Debug + Add<&'a I, Output=I> + ModInv<I> + Integer
-{
-    type Output = Mod<I>;
+    // In place version of `checked_div`. Leaves self untouched on error.
+    fn checked_div_assign(&mut self, rhs: &Mod<I>) -> Result<(), ModError<I>> where I: ModMult {
+        self.check_modulus(rhs)?;
 
-    fn add(self, rhs: Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+        let rhs_inv = rhs.checked_inv()?;
+        self.value = self.value.mod_mult(&rhs_inv.value, &self.modulus);
 
-        let new_value = (rhs.value + &self.value).mod_floor(&self.modulus);
+        Ok(())
+    }
 
-        Mod::new(&new_value, &self.modulus)
+    /// Computes the multiplicative inverse of the residue class, returning an
+    /// error that carries the common factor when there is none.
+    pub fn checked_inv(&self) -> Result<Mod<I>, ModError<I>> {
+        self.value.try_mod_inv(&self.modulus).map(|val| {
+            Mod::new(&val, &self.modulus)
+        })
     }
-}
 
-impl<'a, 'b, I> Add<&'a Mod<I>> for &'b Mod<I>
-    where I: Clone + Eq + Debug + Add<&'a I, Output=I> + ModInv<I> + Integer,
-          &'b I: Add<&'a I, Output=I>
-{
-    type Output = Mod<I>;
+    /// Computes ```self ^ exponent``` for an exponent of the residue type. A
+    /// negative exponent raises the inverse to ```|exponent|```.
+    ///
+    /// # Panics
+    /// when the modulus is negative, or the exponent is negative and the
+    /// residue is not invertible.
+    pub fn pow(&self, exponent: &I) -> Mod<I> where I: ModExp + Exponent {
+        self.checked_pow(exponent).unwrap_or_else(|err| panic!("{:?}", err))
+    }
 
-    fn add(self, rhs: &'a Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+    /// Computes ```self ^ exponent```, returning an error instead of panicking.
+    ///
+    /// # Errors
+    /// `ModError::NegativeInput` when the modulus is negative, and
+    /// `ModError::NotInvertible` when the exponent is negative and the residue
+    /// is not invertible.
+    pub fn checked_pow(&self, exponent: &I) -> Result<Mod<I>, ModError<I>> where I: ModExp + Exponent {
+        Ok(Mod {
+            value: self.value.checked_mod_exp(exponent, &self.modulus)?,
+            modulus: self.modulus.clone(),
+        })
+    }
 
-        let new_value = (&self.value + &rhs.value).mod_floor(&self.modulus);
+    /// Computes ```self ^ exponent``` for a `u64` exponent.
+    ///
+    /// # Panics
+    /// when the modulus is negative.
+    pub fn pow_u64(&self, exponent: u64) -> Mod<I> where I: ModExp {
+        Mod {
+            value: self.value.mod_exp(&exponent, &self.modulus),
+            modulus: self.modulus.clone(),
+        }
+    }
 
-        Mod::new(&new_value, &self.modulus)
+    /// Computes ```self ^ exponent``` for a `BigUint` exponent.
+    ///
+    /// # Panics
+    /// when the modulus is negative.
+    pub fn pow_biguint(&self, exponent: &BigUint) -> Mod<I> where I: ModExp {
+        Mod {
+            value: self.value.mod_exp(exponent, &self.modulus),
+            modulus: self.modulus.clone(),
+        }
     }
 }
 
-impl<I> Mul<Mod<I>> for Mod<I>
-    where I: Clone + Eq + Debug + Mul<I, Output=I> + ModInv<I> + ModMult + Integer
-{
-    type Output = Mod<I>;
-
-    fn mul(self, rhs: Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+impl<I> Mod<I> where I: Clone + Eq + Debug {
+    // Checks that two residues share a modulus.
+    #[inline]
+    fn check_modulus(&self, rhs: &Mod<I>) -> Result<(), ModError<I>> {
+        if self.modulus == rhs.modulus {
+            Ok(())
+        } else {
+            Err(ModError::ModulusMismatch {
+                left: self.modulus.clone(),
+                right: rhs.modulus.clone(),
+            })
+        }
+    }
 
-        let new_value = self.value.mod_mult(&rhs.value, &self.modulus);
-        
-        Mod::new(&new_value, &self.modulus)
+    /// Computes ```self + rhs```, returning an error instead of panicking when
+    /// the moduli differ.
+    pub fn checked_add(&self, rhs: &Mod<I>) -> Result<Mod<I>, ModError<I>> where I: ModAdd {
+        self.check_modulus(rhs).map(|_| {
+            Mod {
+                value: self.value.mod_add(&rhs.value, &self.modulus),
+                modulus: self.modulus.clone(),
+            }
+        })
     }
-}
 
-impl<'a, I> Mul<&'a Mod<I>> for Mod<I>
-    where I: Clone + Eq + Debug + Mul<&'a I, Output=I> + ModInv<I> + ModMult + Integer
-{
-    type Output = Mod<I>;
+    /// Computes ```self - rhs```, returning an error instead of panicking when
+    /// the moduli differ.
+    pub fn checked_sub(&self, rhs: &Mod<I>) -> Result<Mod<I>, ModError<I>> where I: ModSub {
+        self.check_modulus(rhs).map(|_| {
+            Mod {
+                value: self.value.mod_sub(&rhs.value, &self.modulus),
+                modulus: self.modulus.clone(),
+            }
+        })
+    }
 
-    fn mul(self, rhs: &'a Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+    /// Computes ```self * rhs```, returning an error instead of panicking when
+    /// the moduli differ.
+    pub fn checked_mul(&self, rhs: &Mod<I>) -> Result<Mod<I>, ModError<I>> where I: ModMult {
+        self.check_modulus(rhs).map(|_| {
+            Mod {
+                value: self.value.mod_mult(&rhs.value, &self.modulus),
+                modulus: self.modulus.clone(),
+            }
+        })
+    }
 
-        let new_value = self.value.mod_mult(&rhs.value, &self.modulus);
+    // In place versions of the checked methods, used by the compound
+    // assignment operators so that neither operand is cloned.
+    fn checked_add_assign(&mut self, rhs: &Mod<I>) -> Result<(), ModError<I>> where I: ModAdd {
+        self.check_modulus(rhs)?;
+        self.value = self.value.mod_add(&rhs.value, &self.modulus);
 
-        Mod::new(&new_value, &self.modulus)
+        Ok(())
     }
-}
 
-impl<'a, I> Mul<Mod<I>> for &'a Mod<I>
-    where I: Clone + Eq + Debug + Mul<&'a I, Output=I> + ModInv<I> + ModMult + Integer
-{
-    type Output = Mod<I>;
+    fn checked_sub_assign(&mut self, rhs: &Mod<I>) -> Result<(), ModError<I>> where I: ModSub {
+        self.check_modulus(rhs)?;
+        self.value = self.value.mod_sub(&rhs.value, &self.modulus);
 
-    fn mul(self, rhs: Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+        Ok(())
+    }
 
-        let new_value = self.value.mod_mult(&rhs.value, &self.modulus);
+    fn checked_mul_assign(&mut self, rhs: &Mod<I>) -> Result<(), ModError<I>> where I: ModMult {
+        self.check_modulus(rhs)?;
+        self.value = self.value.mod_mult(&rhs.value, &self.modulus);
 
-        Mod::new(&new_value, &self.modulus)
+        Ok(())
     }
 }
 
-impl<'a, 'b, I> Mul<&'a Mod<I>> for &'b Mod<I>
-    where I: Clone + Eq + Debug + Mul<&'a I, Output=I> + ModInv<I> + ModMult + Integer,
-          &'b I: ModMult
-{
-    type Output = Mod<I>;
+// Macro for generating every owned and borrowed combination of a binary
+// operator on Mod, along with its compound assignment operator. All of them
+// defer to the checked methods, and panic with their error. An owned left
+// operand is updated in place and returned.
+macro_rules! mod_binop {
+    ( impl $ Op : ident, $ op : ident, $ checked : ident,
+      impl $ OpAssign : ident, $ op_assign : ident, $ checked_assign : ident
+      where $ ( $ bound : tt ) + ) => {
+        impl<'a, I> $Op<&'a Mod<I>> for &Mod<I> where I: $($bound)+ {
+            type Output = Mod<I>;
+
+            fn $op(self, rhs: &'a Mod<I>) -> Mod<I> {
+                self.$checked(rhs).unwrap_or_else(|err| panic!("{:?}", err))
+            }
+        }
 
-    fn mul(self, rhs: &'a Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+        impl<I> $Op<Mod<I>> for Mod<I> where I: $($bound)+ {
+            type Output = Mod<I>;
 
-        let new_value = self.value.mod_mult(&rhs.value, &self.modulus);
+            fn $op(mut self, rhs: Mod<I>) -> Mod<I> {
+                self.$op_assign(&rhs);
+                self
+            }
+        }
+
+        impl<'a, I> $Op<&'a Mod<I>> for Mod<I> where I: $($bound)+ {
+            type Output = Mod<I>;
 
-        Mod::new(&new_value, &self.modulus)
+            fn $op(mut self, rhs: &'a Mod<I>) -> Mod<I> {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl<I> $Op<Mod<I>> for &Mod<I> where I: $($bound)+ {
+            type Output = Mod<I>;
+
+            fn $op(self, rhs: Mod<I>) -> Mod<I> {
+                self.$op(&rhs)
+            }
+        }
+
+        impl<'a, I> $OpAssign<&'a Mod<I>> for Mod<I> where I: $($bound)+ {
+            fn $op_assign(&mut self, rhs: &'a Mod<I>) {
+                self.$checked_assign(rhs).unwrap_or_else(|err| panic!("{:?}", err))
+            }
+        }
+
+        impl<I> $OpAssign<Mod<I>> for Mod<I> where I: $($bound)+ {
+            fn $op_assign(&mut self, rhs: Mod<I>) {
+                self.$op_assign(&rhs)
+            }
+        }
     }
 }
 
-impl<I> Sub<Mod<I>> for Mod<I>
-    where I: Clone + Eq + Debug + Sub<I, Output=I> + ModInv<I> + Integer
-{
-    type Output = Mod<I>;
+mod_binop!(impl Add, add, checked_add, impl AddAssign, add_assign, checked_add_assign
+           where Clone + Eq + Debug + ModAdd);
+mod_binop!(impl Sub, sub, checked_sub, impl SubAssign, sub_assign, checked_sub_assign
+           where Clone + Eq + Debug + ModSub);
+mod_binop!(impl Mul, mul, checked_mul, impl MulAssign, mul_assign, checked_mul_assign
+           where Clone + Eq + Debug + ModMult);
+mod_binop!(impl Div, div, checked_div, impl DivAssign, div_assign, checked_div_assign
+           where Clone + Eq + Debug + Integer + ModMult + ModInv<I>);
+
+// Macro for generating Sum and Product over owned and borrowed residues. The
+// modulus is taken from the first element, so the fold starts from it rather
+// than from an identity element.
+macro_rules! mod_fold {
+    ( impl $ Fold : ident, $ fold : ident, $ op_assign : ident, $ name : expr ;
+      where $ ( $ bound : tt ) + ) => {
+        impl<I> $Fold<Mod<I>> for Mod<I> where I: $($bound)+ {
+            #[doc = concat!("Computes the ", $name, " of the residues, reduced by the modulus of the first one.")]
+            ///
+            /// # Panics
+            ///
+            /// Panics if the iterator is empty, since there is then no modulus to
+            /// reduce by, or if the residues do not all share the same modulus.
+            fn $fold<It: Iterator<Item=Mod<I>>>(mut iter: It) -> Mod<I> {
+                let mut acc = iter.next().unwrap_or_else(|| {
+                    panic!("cannot compute the {} of an empty iterator of residues", $name)
+                });
+                for x in iter {
+                    acc.$op_assign(&x);
+                }
+
+                acc
+            }
+        }
+
+        impl<'a, I> $Fold<&'a Mod<I>> for Mod<I> where I: $($bound)+ {
+            #[doc = concat!("Computes the ", $name, " of the residues, reduced by the modulus of the first one.")]
+            ///
+            /// # Panics
+            ///
+            /// Panics if the iterator is empty, since there is then no modulus to
+            /// reduce by, or if the residues do not all share the same modulus.
+            fn $fold<It: Iterator<Item=&'a Mod<I>>>(mut iter: It) -> Mod<I> {
+                let mut acc = iter.next().cloned().unwrap_or_else(|| {
+                    panic!("cannot compute the {} of an empty iterator of residues", $name)
+                });
+                for x in iter {
+                    acc.$op_assign(x);
+                }
+
+                acc
+            }
+        }
+    }
+}
 
-    fn sub(self, rhs: Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+// Implementations of Sum and Product. An empty iterator has no modulus to
+// reduce by, so these panic on one; fold from `Mod::zero` or `Mod::one` when
+// the iterator may be empty.
+mod_fold!(impl Sum, sum, add_assign, "sum"; where Clone + Eq + Debug + ModAdd);
+mod_fold!(impl Product, product, mul_assign, "product"; where Clone + Eq + Debug + ModMult);
 
-        let new_value = (self.value + rhs.value).mod_floor(&self.modulus);
+impl<I> Neg for Mod<I>
+    where I: Clone + Debug + ModNeg
+{
+    type Output = Mod<I>;
 
-        Mod::new(&new_value, &self.modulus)
+    fn neg(self) -> Mod<I> {
+        -&self
     }
 }
 
-impl<'a, I> Sub<&'a Mod<I>> for Mod<I>
-    where I: Clone + Eq + Debug + Sub<&'a I, Output=I> + ModInv<I> + Integer
+impl<I> Neg for &Mod<I>
+    where I: Clone + Debug + ModNeg
 {
     type Output = Mod<I>;
 
-    fn sub(self, rhs: &'a Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+    fn neg(self) -> Mod<I> {
+        Mod {
+            value: self.value.mod_neg(&self.modulus),
+            modulus: self.modulus.clone(),
+        }
+    }
+}
 
-        let new_value = (self.value - &rhs.value).mod_floor(&self.modulus);
 
-        Mod::new(&new_value, &self.modulus)
+impl<I> Display for Mod<I> where I: Clone + Debug + Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (mod {})", self.value, self.modulus)
     }
 }
 
-impl<'a, I> Sub<Mod<I>> for &'a Mod<I>
-    where I: Clone + Eq + Debug + Sub<&'a I, Output=I> + ModInv<I> + Integer,
-          &'a I: Sub<I, Output=I>
+impl<I> FromStr for Mod<I>
+    where I: Clone + Debug + Eq + ModInv<I> + Integer + FromStr
 {
-    type Output = Mod<I>;
+    type Err = ParseModError<<I as FromStr>::Err>;
 
-    fn sub(self, rhs: Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+    /// Parses a residue class of the form ```value (mod modulus)```, as
+    /// produced by `Display`. The value is reduced modulo the modulus.
+    fn from_str(s: &str) -> Result<Mod<I>, Self::Err> {
+        let s = s.trim();
+        let open = s.find('(').ok_or(ParseModError::InvalidFormat)?;
+        let inner = s[open + 1..].strip_suffix(')').ok_or(ParseModError::InvalidFormat)?;
+        let modulus = inner.trim_start().strip_prefix("mod").ok_or(ParseModError::InvalidFormat)?;
 
-        let new_value = (&self.value - rhs.value).mod_floor(&self.modulus);
+        let value = I::from_str(s[..open].trim()).map_err(ParseModError::InvalidInteger)?;
+        let modulus = I::from_str(modulus.trim()).map_err(ParseModError::InvalidInteger)?;
 
-        Mod::new(&new_value, &self.modulus)
+        if modulus.is_zero() {
+            return Err(ParseModError::ZeroModulus);
+        }
+
+        Ok(Mod::new(&value, &modulus))
     }
 }
 
-impl<'a, 'b, I> Sub<&'a Mod<I>> for &'b Mod<I>
-    where I: Clone + Eq + Debug + Sub<&'a I, Output=I> + ModInv<I> + Integer,
-          &'b I: Sub<&'a I, Output=I>
-{
-    type Output = Mod<I>;
-
-    fn sub(self, rhs: &'a Mod<I>) -> Mod<I> {
-        assert_eq!(self.modulus, rhs.modulus);
+impl<I> PartialOrd for Mod<I> where I: Clone + Debug + Ord {
+    fn partial_cmp(&self, other: &Mod<I>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-        let new_value = (&self.value - &rhs.value).mod_floor(&self.modulus);
+impl<I> Ord for Mod<I> where I: Clone + Debug + Ord {
+    fn cmp(&self, other: &Mod<I>) -> Ordering {
+        self.modulus.cmp(&other.modulus).then_with(|| self.value.cmp(&other.value))
+    }
+}
 
-        Mod::new(&new_value, &self.modulus)
+// Macro for generating the num Pow implementations for owned and borrowed
+// residues and exponents. These follow the inherent `pow`, and panic when the
+// exponent is negative and the residue is not invertible.
+macro_rules! mod_pow {
+    ( $ ( $ Lhs : ty , $ Rhs : ty ) ; + ) => {
+        $(
+            impl<'a, 'b, I> traits::Pow<$Rhs> for $Lhs
+                where I: Clone + Eq + Debug + Integer + ModInv<I> + ModExp + Exponent
+            {
+                type Output = Mod<I>;
+
+                fn pow(self, exponent: $Rhs) -> Mod<I> {
+                    Mod::pow(&self, &exponent)
+                }
+            }
+        )+
     }
 }
 
-impl<I> Neg for Mod<I> 
-    where I: Eq + Clone + Debug + ModInv<I> + Neg<Output=I> + Integer
-{    
+mod_pow!(Mod<I>, I; Mod<I>, &'a I; &'b Mod<I>, I; &'b Mod<I>, &'a I);
+
+impl<I> traits::Inv for Mod<I>
+    where I: Clone + Eq + Debug + Integer + ModInv<I>
+{
     type Output = Mod<I>;
 
-    fn neg(self) -> Mod<I> {
-        Mod::new(&(-self.value), &self.modulus)
+    fn inv(self) -> Mod<I> {
+        traits::Inv::inv(&self)
     }
-} 
+}
 
-impl<'a, I> Neg for &'a Mod<I> 
-    where I: Eq + Clone + Debug + ModInv<I> + Integer,
-          &'a I: Neg<Output=I>
+impl<I> traits::Inv for &Mod<I>
+    where I: Clone + Eq + Debug + Integer + ModInv<I>
 {
     type Output = Mod<I>;
 
-    fn neg(self) -> Mod<I> {
-        let neg_val = - &self.value;
+    /// Computes the multiplicative inverse of the residue class.
+    ///
+    /// # Panics
+    /// when the residue class is not invertible.
+    fn inv(self) -> Mod<I> {
+        self.checked_inv().unwrap_or_else(|err| panic!("{:?}", err))
+    }
+}
 
-        Mod::new(&neg_val, &self.modulus)
+// Macro for generating the num checked arithmetic traits, which return
+// ```None``` wherever the corresponding checked method returns an error.
+macro_rules! mod_checked_op {
+    ( impl $ Trait : ident, $ method : ident where $ ( $ bound : tt ) + ) => {
+        impl<I> traits::$Trait for Mod<I> where I: $($bound)+ {
+            fn $method(&self, rhs: &Mod<I>) -> Option<Mod<I>> {
+                Mod::$method(self, rhs).ok()
+            }
+        }
     }
 }
 
+mod_checked_op!(impl CheckedAdd, checked_add where Clone + Eq + Debug + ModAdd);
+mod_checked_op!(impl CheckedSub, checked_sub where Clone + Eq + Debug + ModSub);
+mod_checked_op!(impl CheckedMul, checked_mul where Clone + Eq + Debug + ModMult);
+mod_checked_op!(impl CheckedDiv, checked_div where Clone + Eq + Debug + Integer + ModMult + ModInv<I>);
 
 #[cfg(test)]
 mod tests {
+    use num::{BigInt, BigUint};
+    use num::traits;
+    use modadd::ModAdd;
+    use modsub::ModSub;
+    use modmult::ModMult;
+    use modneg::ModNeg;
+    use error::{ModError, ParseModError};
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use std::panic;
+    use super::Mod;
+
+
+    // A xorshift generator, so the property tests below are reproducible
+    // without extra dependencies.
+    struct XorShift {
+        state: u64,
+    }
+
+    impl XorShift {
+        fn new(seed: u64) -> XorShift {
+            XorShift { state: seed }
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state
+        }
+
+        // Draws from a mix of small and full width values, so both the common
+        // case and the overflow prone edges get exercised.
+        fn next_value(&mut self) -> u64 {
+            match self.next_u64() % 3 {
+                0 => self.next_u64() % 64,
+                1 => self.next_u64() >> 32,
+                _ => self.next_u64(),
+            }
+        }
+    }
+
+    struct Expected<I> {
+        sum:  I,
+        diff: I,
+        prod: I,
+        neg:  I,
+    }
+
+    // Checks every owned and borrowed combination of each operator against
+    // the expected values.
+    fn check_operator_matrix<I>(x: &Mod<I>, y: &Mod<I>, expected: &Expected<I>)
+        where I: Clone + Eq + Debug + ModAdd + ModSub + ModMult + ModNeg
+    {
+        let sums = [x.clone() + y.clone(), x.clone() + y, x + y.clone(), x + y];
+        let diffs = [x.clone() - y.clone(), x.clone() - y, x - y.clone(), x - y];
+        let prods = [x.clone() * y.clone(), x.clone() * y, x * y.clone(), x * y];
+        let negs = [-x.clone(), -x];
+
+        for result in sums.iter() {
+            assert_eq!(*result.value(), expected.sum);
+            assert_eq!(result.modulus(), x.modulus());
+        }
+        for result in diffs.iter() {
+            assert_eq!(*result.value(), expected.diff);
+            assert_eq!(result.modulus(), x.modulus());
+        }
+        for result in prods.iter() {
+            assert_eq!(*result.value(), expected.prod);
+            assert_eq!(result.modulus(), x.modulus());
+        }
+        for result in negs.iter() {
+            assert_eq!(*result.value(), expected.neg);
+            assert_eq!(result.modulus(), x.modulus());
+        }
+
+        assert_eq!(*x.checked_add(y).unwrap().value(), expected.sum);
+        assert_eq!(*x.checked_sub(y).unwrap().value(), expected.diff);
+        assert_eq!(*x.checked_mul(y).unwrap().value(), expected.prod);
+    }
+
+    // Checks that every combination of each operator panics, and that the
+    // checked methods report the mismatch, for residues with different moduli.
+    fn check_mismatch_matrix<I>(x: &Mod<I>, y: &Mod<I>)
+        where I: Clone + Eq + Debug + ModAdd + ModSub + ModMult + panic::RefUnwindSafe
+    {
+        let combinations: Vec<Box<dyn Fn() -> Mod<I>>> = vec![
+            Box::new(|| x.clone() + y.clone()),
+            Box::new(|| x.clone() + y),
+            Box::new(|| x + y.clone()),
+            Box::new(|| x + y),
+            Box::new(|| x.clone() - y.clone()),
+            Box::new(|| x.clone() - y),
+            Box::new(|| x - y.clone()),
+            Box::new(|| x - y),
+            Box::new(|| x.clone() * y.clone()),
+            Box::new(|| x.clone() * y),
+            Box::new(|| x * y.clone()),
+            Box::new(|| x * y),
+        ];
+
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        for combination in combinations.iter() {
+            assert!(panic::catch_unwind(panic::AssertUnwindSafe(combination)).is_err());
+        }
+        panic::set_hook(hook);
+
+        let mismatch = ModError::ModulusMismatch {
+            left: x.modulus().clone(),
+            right: y.modulus().clone(),
+        };
+
+        assert_eq!(x.checked_add(y).unwrap_err(), mismatch);
+        assert_eq!(x.checked_sub(y).unwrap_err(), mismatch);
+        assert_eq!(x.checked_mul(y).unwrap_err(), mismatch);
+    }
+
+    #[test]
+    fn test_mod_operator_matrix_i64() {
+        let mut rng = XorShift::new(0x9e3779b97f4a7c15);
+
+        for _ in 0..500 {
+            let a = rng.next_value() as i64;
+            let b = rng.next_value() as i64;
+            let modulus = ((rng.next_value() >> 1) as i64).max(1);
+            let m = modulus as i128;
+            let (a_r, b_r) = ((a as i128).rem_euclid(m), (b as i128).rem_euclid(m));
+            let expected = Expected {
+                sum:  (a_r + b_r).rem_euclid(m) as i64,
+                diff: (a_r - b_r).rem_euclid(m) as i64,
+                prod: (a_r * b_r).rem_euclid(m) as i64,
+                neg:  (-a_r).rem_euclid(m) as i64,
+            };
+
+            check_operator_matrix(&Mod::new(&a, &modulus), &Mod::new(&b, &modulus), &expected);
+
+            let expected = Expected {
+                sum:  BigInt::from(expected.sum),
+                diff: BigInt::from(expected.diff),
+                prod: BigInt::from(expected.prod),
+                neg:  BigInt::from(expected.neg),
+            };
+            let modulus = BigInt::from(modulus);
+
+            check_operator_matrix(&Mod::new(&BigInt::from(a), &modulus),
+                                  &Mod::new(&BigInt::from(b), &modulus), &expected);
+        }
+    }
+
+    #[test]
+    fn test_mod_operator_matrix_u64() {
+        let mut rng = XorShift::new(0x2545f4914f6cdd1d);
+
+        for _ in 0..500 {
+            let a = rng.next_value();
+            let b = rng.next_value();
+            let modulus = rng.next_value().max(1);
+            let m = modulus as u128;
+            let (a_r, b_r) = (a as u128 % m, b as u128 % m);
+            let expected = Expected {
+                sum:  ((a_r + b_r) % m) as u64,
+                diff: ((a_r + m - b_r) % m) as u64,
+                prod: ((a_r * b_r) % m) as u64,
+                neg:  ((m - a_r) % m) as u64,
+            };
+
+            check_operator_matrix(&Mod::new(&a, &modulus), &Mod::new(&b, &modulus), &expected);
+
+            let expected = Expected {
+                sum:  BigUint::from(expected.sum),
+                diff: BigUint::from(expected.diff),
+                prod: BigUint::from(expected.prod),
+                neg:  BigUint::from(expected.neg),
+            };
+            let modulus = BigUint::from(modulus);
+
+            check_operator_matrix(&Mod::new(&BigUint::from(a), &modulus),
+                                  &Mod::new(&BigUint::from(b), &modulus), &expected);
+        }
+    }
+
+    #[test]
+    fn test_mod_modulus_mismatch_matrix() {
+        let mut rng = XorShift::new(0x853c49e6748fea9b);
+
+        for _ in 0..20 {
+            let a = rng.next_value();
+            let b = rng.next_value();
+            let modulus = rng.next_value().max(2);
+
+            check_mismatch_matrix(&Mod::new(&a, &modulus), &Mod::new(&b, &(modulus - 1)));
+            check_mismatch_matrix(&Mod::new(&BigInt::from(a), &BigInt::from(modulus)),
+                                  &Mod::new(&BigInt::from(b), &BigInt::from(modulus + 1)));
+        }
+    }
+
+    #[test]
+    fn test_mod_constructors() {
+        let modulus = BigInt::from(29);
+        let x = Mod::new(&BigInt::from(-1), &modulus);
+
+        assert_eq!(*x.value(), BigInt::from(28));
+        assert_eq!(*x.modulus(), modulus);
+        assert_eq!(x.un_mod(), BigInt::from(28));
+        assert_eq!(*Mod::zero(&modulus).value(), BigInt::from(0));
+        assert_eq!(*Mod::one(&modulus).value(), BigInt::from(1));
+        assert_eq!(Mod::try_new(&BigInt::from(-1), &modulus), Ok(x));
+        assert_eq!(Mod::try_new(&5u32, &0), Err(ModError::ZeroModulus));
+
+        // A negative modulus keeps values in (m, 0].
+        assert_eq!(*Mod::new(&3i64, &-7).value(), -4);
+        assert_eq!(*Mod::new(&-3i64, &-7).value(), -3);
+        assert_eq!(*Mod::new(&BigInt::from(14), &BigInt::from(-7)).value(), BigInt::from(0));
+    }
+
+    #[test]
+    fn test_mod_operators() {
+        let modulus = 29i64;
+        let x = Mod::new(&17, &modulus);
+        let y = Mod::new(&8, &modulus);
+
+        assert_eq!(*(x.clone() + y.clone()).value(), 25);
+        assert_eq!(*(x.clone() - y.clone()).value(), 9);
+        assert_eq!(*(y.clone() - x.clone()).value(), 20);
+        assert_eq!(*(x.clone() * y.clone()).value(), 20);
+        assert_eq!(*(-x.clone()).value(), 12);
+        assert_eq!(*(&x / &y * &y).value(), 17);
+    }
+
+    #[test]
+    fn test_mod_biguint() {
+        let modulus = BigUint::from(29u32);
+        let x = Mod::new(&BigUint::from(3u32), &modulus);
+        let y = Mod::new(&BigUint::from(8u32), &modulus);
+
+        assert_eq!(*(&x - &y).value(), BigUint::from(24u32));
+        assert_eq!(*(-&x).value(), BigUint::from(26u32));
+        assert_eq!(*x.inv().unwrap().value(), BigUint::from(10u32));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_modulus_mismatch() {
+        let x = Mod::new(&3i32, &29);
+        let y = Mod::new(&3i32, &31);
 
-} 
\ No newline at end of file
+        let _ = x + y;
+    }
+
+    #[test]
+    fn test_mod_checked_div() {
+        let x = Mod::new(&3i32, &30);
+        let y = Mod::new(&6i32, &30);
+        let z = Mod::new(&7i32, &30);
+
+        assert_eq!(x.checked_div(&y).unwrap_err(), ModError::NotInvertible { gcd: 6 });
+        assert_eq!(*x.checked_div(&z).unwrap().value(), 9);
+        assert!(x.checked_div(&Mod::new(&7, &31)).is_err());
+
+        let mut w = x.clone();
+        w /= &z;
+        w /= z.clone();
+
+        assert_eq!(*w.value(), 27);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        let modulus = BigInt::from(29);
+        let x = Mod::new(&BigInt::from(2), &modulus);
+
+        assert_eq!(*x.pow(&BigInt::from(8)).value(), BigInt::from(24));
+        assert_eq!(*x.pow(&BigInt::from(-8)).value(), BigInt::from(23));
+        assert_eq!(*x.pow(&BigInt::from(0)).value(), BigInt::from(1));
+        assert_eq!(*x.pow_u64(8).value(), BigInt::from(24));
+        assert_eq!(*x.pow_biguint(&BigUint::from(8u32)).value(), BigInt::from(24));
+        assert_eq!(*(&x.pow(&BigInt::from(-3)) * &x.pow(&BigInt::from(3))).value(), BigInt::from(1));
+
+        let y = Mod::new(&6i64, &30);
+
+        assert_eq!(y.checked_pow(&-1).unwrap_err(), ModError::NotInvertible { gcd: 6 });
+        assert_eq!(*y.pow(&2).value(), 6);
+        assert_eq!(*Mod::new(&3u64, &1).pow_u64(0).value(), 0);
+        assert_eq!(*Mod::new(&3i64, &1000003).pow_u64(1000002).value(), 1);
+        assert_eq!(*Mod::new(&3u8, &251).pow_biguint(&BigUint::from(1u64 << 40)).value(), 88);
+        assert_eq!(Mod::new(&3i64, &-7).checked_pow(&2), Err(ModError::NegativeInput));
+        assert_eq!(Mod::new(&BigInt::from(3), &BigInt::from(-7)).checked_pow(&BigInt::from(-2)), Err(ModError::NegativeInput));
+    }
+
+    #[test]
+    fn test_mod_assign_operators() {
+        let modulus = BigInt::from(29);
+        let x = Mod::new(&BigInt::from(17), &modulus);
+        let y = Mod::new(&BigInt::from(-8), &modulus);
+        let mut z = x.clone();
+
+        z += &y;
+        assert_eq!(*z.value(), BigInt::from(9));
+        z -= y.clone();
+        assert_eq!(*z.value(), BigInt::from(17));
+        z *= &y;
+        assert_eq!(*z.value(), BigInt::from(9));
+        z /= y.clone();
+        assert_eq!(*z.value(), BigInt::from(17));
+        z *= y;
+        assert_eq!(z.modulus(), &modulus);
+        assert_eq!(*z.value(), BigInt::from(9));
+    }
+
+    #[test]
+    fn test_mod_sum_product() {
+        let residues: Vec<Mod<u64>> = (1..11u64).map(|x| Mod::new(&x, &13)).collect();
+
+        // 55 == 3 (mod 13), and 10! == 6 (mod 13).
+        assert_eq!(*residues.iter().sum::<Mod<u64>>().value(), 3);
+        assert_eq!(*residues.iter().product::<Mod<u64>>().value(), 6);
+        assert_eq!(*residues.clone().into_iter().sum::<Mod<u64>>().value(), 3);
+        assert_eq!(*residues.into_iter().product::<Mod<u64>>().value(), 6);
+
+        let empty: Vec<Mod<u64>> = Vec::new();
+        let folded = empty.iter().fold(Mod::one(&13), |acc, x| acc * x);
+
+        assert_eq!(*folded.value(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty iterator")]
+    fn test_mod_sum_empty() {
+        let empty: Vec<Mod<u64>> = Vec::new();
+
+        empty.into_iter().sum::<Mod<u64>>();
+    }
+
+    #[test]
+    #[should_panic(expected = "empty iterator")]
+    fn test_mod_product_empty() {
+        let empty: Vec<Mod<u64>> = Vec::new();
+
+        empty.iter().product::<Mod<u64>>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_assign_modulus_mismatch() {
+        let mut x = Mod::new(&3u64, &7);
+
+        x += Mod::new(&3u64, &11);
+    }
+
+    #[test]
+    fn test_mod_display_from_str() {
+        let x = Mod::new(&-2i64, &7);
+
+        assert_eq!(x.to_string(), "5 (mod 7)");
+        assert_eq!("5 (mod 7)".parse::<Mod<i64>>(), Ok(x.clone()));
+        assert_eq!("  12(mod  7 ) ".parse::<Mod<i64>>(), Ok(x));
+
+        let y: Mod<BigInt> = "-1 (mod 340282366920938463463374607431768211457)".parse().unwrap();
+
+        assert_eq!(y.to_string(), "340282366920938463463374607431768211456 (mod 340282366920938463463374607431768211457)");
+        assert_eq!(y.to_string().parse::<Mod<BigInt>>().unwrap(), y);
+
+        assert_eq!("5 mod 7".parse::<Mod<u32>>(), Err(ParseModError::InvalidFormat));
+        assert_eq!("5 (7)".parse::<Mod<u32>>(), Err(ParseModError::InvalidFormat));
+        assert_eq!("5 (mod 0)".parse::<Mod<u32>>(), Err(ParseModError::ZeroModulus));
+        assert!(matches!("x (mod 7)".parse::<Mod<u32>>(), Err(ParseModError::InvalidInteger(_))));
+    }
+
+    #[test]
+    fn test_mod_eq_hash_ord() {
+        let mut table = HashMap::new();
+
+        for i in 0..20u64 {
+            table.insert(Mod::new(&(i * i), &11), i);
+        }
+
+        // Squares mod 11 take six distinct values, each hit by i and -i.
+        assert_eq!(table.len(), 6);
+        assert!(table.contains_key(&Mod::new(&3, &11)));
+        assert!(!table.contains_key(&Mod::new(&3, &13)));
+
+        assert_eq!(Mod::new(&3u64, &11), Mod::new(&14, &11));
+        assert!(Mod::new(&3u64, &11) != Mod::new(&3, &13));
+        assert!(Mod::new(&10u64, &11) < Mod::new(&3, &13));
+        assert!(Mod::new(&4u64, &11) > Mod::new(&3, &11));
+
+        let mut residues = vec![Mod::new(&5u64, &7), Mod::new(&1, &11), Mod::new(&2, &7)];
+        residues.sort();
+
+        assert_eq!(residues, vec![Mod::new(&2u64, &7), Mod::new(&5, &7), Mod::new(&1, &11)]);
+    }
+
+    #[test]
+    fn test_mod_num_traits() {
+        let modulus = BigInt::from(29);
+        let x = Mod::new(&BigInt::from(2), &modulus);
+        let y = Mod::new(&6i64, &30);
+
+        assert_eq!(traits::Pow::pow(&x, BigInt::from(-8)), Mod::new(&BigInt::from(23), &modulus));
+        assert_eq!(traits::Pow::pow(x.clone(), &BigInt::from(8)), Mod::new(&BigInt::from(24), &modulus));
+        assert_eq!(traits::Inv::inv(&x), Mod::new(&BigInt::from(15), &modulus));
+        assert_eq!(traits::CheckedDiv::checked_div(&y, &y), None);
+        assert_eq!(traits::CheckedAdd::checked_add(&y, &Mod::new(&1, &7)), None);
+        assert_eq!(traits::CheckedMul::checked_mul(&y, &y), Some(Mod::new(&6, &30)));
+
+        assert_eq!(Mod::from_str_radix("-ff", 16, &7), Ok(Mod::new(&4, &7)));
+        assert!(Mod::from_str_radix("fg", 16, &7i32).is_err());
+        assert!(Mod::zero(&7u32).is_zero());
+        assert!(Mod::new(&8u32, &7).is_one());
+        assert!(Mod::new(&0u32, &1).is_one());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_inv_non_invertible() {
+        traits::Inv::inv(Mod::new(&6i64, &30));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mod_div_non_invertible() {
+        let x = Mod::new(&3i32, &30);
+        let y = Mod::new(&6i32, &30);
+
+        let _ = x / y;
+    }
+}
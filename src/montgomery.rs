@@ -0,0 +1,679 @@
+use num::{One, Integer, BigUint, ToPrimitive};
+use std::ops::{Add, Sub, Mul};
+use modexp::{Exponent, sliding_window_exp};
+use modinv::ModInv;
+use error::ModError;
+use std::fmt::Display;
+use std::ptr;
+
+
+// Number of bits in a limb of a BigUint.
+const LIMB_BITS: usize = 32;
+
+/// A data structure storing the precomputed constants for Montgomery
+/// multiplication modulo an odd modulus m.
+///
+/// Given a modulus m occupying n limbs, the Montgomery radix is
+/// ```text
+/// R == b ^ n
+/// ```
+/// where b is the limb base. For `BigUint` the limbs are 32 bits wide, while
+/// `u32` and `u64` moduli use a single limb the width of the type. The
+/// context stores R (mod m), R^2 (mod m), and
+/// ```text
+/// m' == -m ^ -1 (mod b)
+/// ```
+/// so that products can be reduced without ever dividing by m.
+///
+/// See Algorithm 14.32 of the 'Handbook of Applied Cryptography'.
+#[derive(Clone, Debug)]
+pub struct MontgomeryContext<T> {
+    modulus: T,
+    r_mod:   T,
+    r2_mod:  T,
+    m_prime: u64,
+    // The limbs of a multiple limb modulus, least significant limb first.
+    // Empty for the primitive types, which use a single limb.
+    modulus_limbs: Vec<u32>,
+}
+
+// Macro for generating functions computing -x ^ -1 (mod 2^n) for odd x using
+// Newton iteration. Each step doubles the number of correct low bits, starting
+// from the three bits that x is its own inverse modulo.
+macro_rules! neg_inv {
+    ( $ name : ident, $ T : ty, $ steps : expr ) => {
+        #[inline]
+        pub(crate) fn $name(x: $T) -> $T {
+            debug_assert!(x & 1 == 1);
+
+            let mut inv: $T = x;
+            for _ in 0..$steps {
+                inv = inv.wrapping_mul((2 as $T).wrapping_sub(x.wrapping_mul(inv)));
+            }
+
+            inv.wrapping_neg()
+        }
+    }
+}
+
+neg_inv!(neg_inv_u32, u32, 4);
+neg_inv!(neg_inv_u64, u64, 5);
+
+/// The `MontgomeryInt` trait defines the integer types that Montgomery
+/// contexts can be built for. The methods are the type specific parts of the
+/// algorithm; the rest lives on `MontgomeryContext`.
+pub trait MontgomeryInt: Clone + PartialEq {
+    /// Computes the Montgomery constants for the modulus m.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the modulus is even or zero, since then m is not
+    /// coprime to the limb base.
+    fn montgomery_context(modulus: &Self) -> Option<MontgomeryContext<Self>>;
+
+    /// Montgomery reduction of a single width value ```0 <= x < m```, computing
+    /// ```text
+    /// x * R ^ -1 (mod m)
+    /// ```
+    fn montgomery_redc(ctx: &MontgomeryContext<Self>, x: &Self) -> Self;
+
+    /// Computes the Montgomery product ```x * y * R ^ -1 (mod m)``` for
+    /// ```0 <= x, y < m```.
+    fn montgomery_mult(ctx: &MontgomeryContext<Self>, x: &Self, y: &Self) -> Self;
+
+    /// Computes ```x + y (mod m)``` for ```0 <= x, y < m```.
+    fn montgomery_add(ctx: &MontgomeryContext<Self>, x: &Self, y: &Self) -> Self;
+
+    /// Computes ```x - y (mod m)``` for ```0 <= x, y < m```.
+    fn montgomery_sub(ctx: &MontgomeryContext<Self>, x: &Self, y: &Self) -> Self;
+}
+
+impl<T> MontgomeryContext<T> where T: MontgomeryInt {
+    /// Constructs a Montgomery context for the modulus m.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the modulus is even or zero.
+    pub fn new(modulus: &T) -> Option<MontgomeryContext<T>> {
+        T::montgomery_context(modulus)
+    }
+
+    /// Returns the modulus of the context.
+    pub fn modulus(&self) -> &T {
+        &self.modulus
+    }
+
+    /// Converts a value x into Montgomery form ```x * R (mod m)```. The value
+    /// need not be reduced modulo m.
+    pub fn to_montgomery(&self, x: &T) -> T {
+        T::montgomery_mult(self, x, &self.r2_mod)
+    }
+
+    /// Converts a value out of Montgomery form, computing ```x * R ^ -1 (mod m)```.
+    pub fn from_montgomery(&self, x: &T) -> T {
+        T::montgomery_redc(self, x)
+    }
+
+    /// Computes the Montgomery product ```x * y * R ^ -1 (mod m)``` of two values
+    /// in Montgomery form.
+    pub fn montgomery_mult(&self, x: &T, y: &T) -> T {
+        T::montgomery_mult(self, x, y)
+    }
+
+    /// Computes ```x + y (mod m)``` for values in Montgomery form.
+    pub fn montgomery_add(&self, x: &T, y: &T) -> T {
+        T::montgomery_add(self, x, y)
+    }
+
+    /// Computes ```x - y (mod m)``` for values in Montgomery form.
+    pub fn montgomery_sub(&self, x: &T, y: &T) -> T {
+        T::montgomery_sub(self, x, y)
+    }
+
+    /// Returns the Montgomery form of one, i.e. ```R (mod m)```.
+    pub fn one(&self) -> T {
+        self.r_mod.clone()
+    }
+
+    /// Computes ```b ^ e (mod m)``` for a residue ```0 <= b < m``` in ordinary
+    /// form, by left to right sliding window exponentiation in Montgomery form.
+    /// A negative exponent raises the inverse of b to ```|e|```. The result is
+    /// in ordinary form.
+    ///
+    /// # Panics
+    /// when the exponent is negative and b has no inverse modulo m.
+    pub fn mod_exp<E: Exponent + ?Sized>(&self, base: &T, exponent: &E) -> T
+        where T: Integer + ModInv<T> + Display
+    {
+        self.checked_mod_exp(base, exponent).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Computes ```b ^ e (mod m)``` like `mod_exp`, returning an error instead
+    /// of panicking.
+    ///
+    /// # Errors
+    /// `ModError::NotInvertible` with the common factor of b and m when the
+    /// exponent is negative and b has no inverse.
+    pub fn checked_mod_exp<E: Exponent + ?Sized>(&self, base: &T, exponent: &E) -> Result<T, ModError<T>>
+        where T: Integer + ModInv<T>
+    {
+        if exponent.is_negative() {
+            let base_inv = base.try_mod_inv(&self.modulus)?;
+
+            Ok(self.mod_exp_abs(&base_inv, exponent))
+        } else {
+            Ok(self.mod_exp_abs(base, exponent))
+        }
+    }
+
+    // Computes b ^ |e| (mod m), ignoring the sign of the exponent.
+    pub(crate) fn mod_exp_abs<E: Exponent + ?Sized>(&self, base: &T, exponent: &E) -> T {
+        let base = self.to_montgomery(base);
+        let result = sliding_window_exp(self.one(), base, exponent, |x, y| T::montgomery_mult(self, x, y));
+
+        self.from_montgomery(&result)
+    }
+
+    /// Computes ```x * y (mod m)``` for residues ```0 <= x, y < m``` in ordinary
+    /// form. The result is in ordinary form.
+    ///
+    /// This costs two reductions, so a single product is no faster than a
+    /// division. For a chain of products, convert the operands once with
+    /// `to_montgomery` or `MontgomeryForm`, so that each product costs one.
+    pub fn mod_mult(&self, x: &T, y: &T) -> T {
+        // redc(x * y) == x * y * R^-1, and multiplying by R^2 before the second
+        // reduction cancels the remaining factor of R^-1.
+        let xy = T::montgomery_mult(self, x, y);
+
+        T::montgomery_mult(self, &xy, &self.r2_mod)
+    }
+}
+
+// Splits x into at least len 32 bit limbs, least significant limb first.
+fn __to_limbs(x: &BigUint, len: usize) -> Vec<u32> {
+    let bytes = x.to_bytes_le();
+    let mut limbs = vec![0u32; len.max(bytes.len().div_ceil(4))];
+    for (i, chunk) in bytes.chunks(4).enumerate() {
+        for (j, &byte) in chunk.iter().enumerate() {
+            limbs[i] |= (byte as u32) << (8 * j);
+        }
+    }
+
+    limbs
+}
+
+// Montgomery reduction for BigUint. Given 0 <= t < m * R, computes
+// t * R^-1 (mod m) one limb at a time using only multiplications, additions,
+// and shifts. The limbs of t are updated in place, so the reduction allocates
+// only the limb vectors and the result.
+//
+// Larger values are accepted too, so that unreduced inputs still give
+// canonical results: up to 2^(bits(m) + 32n) the result is below 3m, and
+// anything beyond that is divided by m first.
+fn redc_biguint(ctx: &MontgomeryContext<BigUint>, t: &BigUint) -> BigUint {
+    let modulus = &ctx.modulus_limbs;
+    let n = modulus.len();
+
+    if t.bits() > ctx.modulus.bits() + n * LIMB_BITS {
+        return redc_biguint(ctx, &t.mod_floor(&ctx.modulus));
+    }
+
+    // One spare limb catches the carry out of the top of t + u * m.
+    let mut t = __to_limbs(t, 2 * n + 1);
+
+    for i in 0..n {
+        let u = t[i].wrapping_mul(ctx.m_prime as u32) as u64;
+        let mut carry = 0u64;
+        for (j, &m_j) in modulus.iter().enumerate() {
+            let s = (t[i + j] as u64) + u * (m_j as u64) + carry;
+            t[i + j] = s as u32;
+            carry = s >> LIMB_BITS;
+        }
+
+        let mut k = i + n;
+        while carry != 0 {
+            let s = (t[k] as u64) + carry;
+            t[k] = s as u32;
+            carry = s >> LIMB_BITS;
+            k += 1;
+        }
+    }
+
+    let mut result = BigUint::new(t.split_off(n));
+
+    while result >= ctx.modulus {
+        result -= &ctx.modulus;
+    }
+
+    result
+}
+
+impl MontgomeryInt for BigUint {
+    fn montgomery_context(modulus: &BigUint) -> Option<MontgomeryContext<BigUint>> {
+        if modulus.is_even() {
+            return None;
+        }
+
+        let limbs = modulus.bits().div_ceil(LIMB_BITS);
+        let limb_mask = BigUint::from(u32::MAX);
+        let m0 = (modulus & &limb_mask).to_u32().unwrap();
+        let r: BigUint = <BigUint as One>::one() << (limbs * LIMB_BITS);
+        let r_mod = r.mod_floor(modulus);
+        let r2_mod = (&r_mod * &r_mod).mod_floor(modulus);
+
+        Some(MontgomeryContext {
+            modulus: modulus.clone(),
+            r_mod,
+            r2_mod,
+            m_prime: neg_inv_u32(m0) as u64,
+            modulus_limbs: __to_limbs(modulus, limbs),
+        })
+    }
+
+    fn montgomery_redc(ctx: &MontgomeryContext<BigUint>, x: &BigUint) -> BigUint {
+        redc_biguint(ctx, x)
+    }
+
+    fn montgomery_mult(ctx: &MontgomeryContext<BigUint>, x: &BigUint, y: &BigUint) -> BigUint {
+        redc_biguint(ctx, &(x * y))
+    }
+
+    fn montgomery_add(ctx: &MontgomeryContext<BigUint>, x: &BigUint, y: &BigUint) -> BigUint {
+        let sum = x + y;
+
+        if sum >= ctx.modulus {
+            sum - &ctx.modulus
+        } else {
+            sum
+        }
+    }
+
+    fn montgomery_sub(ctx: &MontgomeryContext<BigUint>, x: &BigUint, y: &BigUint) -> BigUint {
+        if x >= y {
+            x - y
+        } else {
+            &ctx.modulus - (y - x)
+        }
+    }
+}
+
+// Montgomery reduction of a double width value 0 <= t < m * R.
+macro_rules! redc {
+    ( $ ctx : expr, $ t : expr, $ T : ty, $ W : ty ) => {{
+        let t: $W = $t;
+        let u = (t as $T).wrapping_mul($ctx.m_prime as $T);
+        // t + u * m can exceed the double width type when m is close to R, so
+        // the carry is tracked separately.
+        let (sum, carry) = t.overflowing_add((u as $W) * ($ctx.modulus as $W));
+        let high = (sum >> <$T>::BITS) as $T;
+
+        if carry || high >= $ctx.modulus {
+            high.wrapping_sub($ctx.modulus)
+        } else {
+            high
+        }
+    }}
+}
+
+// Macro for generating MontgomeryInt implementations for primitive unsigned
+// types. The radix is R == 2^bits, and intermediate products are held in the
+// double width type $W.
+macro_rules! montgomery_int {
+    ( $ T : ty, $ W : ty, $ neg_inv : ident ) => {
+        impl MontgomeryInt for $T {
+            fn montgomery_context(modulus: &$T) -> Option<MontgomeryContext<$T>> {
+                if *modulus & 1 == 0 {
+                    return None;
+                }
+
+                let m = *modulus as $W;
+                let r_mod = ((1 as $W) << <$T>::BITS) % m;
+                let r2_mod = (r_mod * r_mod) % m;
+
+                Some(MontgomeryContext {
+                    modulus: *modulus,
+                    r_mod: r_mod as $T,
+                    r2_mod: r2_mod as $T,
+                    m_prime: $neg_inv(*modulus) as u64,
+                    modulus_limbs: Vec::new(),
+                })
+            }
+
+            fn montgomery_redc(ctx: &MontgomeryContext<$T>, x: &$T) -> $T {
+                redc!(ctx, *x as $W, $T, $W)
+            }
+
+            fn montgomery_mult(ctx: &MontgomeryContext<$T>, x: &$T, y: &$T) -> $T {
+                redc!(ctx, (*x as $W) * (*y as $W), $T, $W)
+            }
+
+            fn montgomery_add(ctx: &MontgomeryContext<$T>, x: &$T, y: &$T) -> $T {
+                if *x >= ctx.modulus - *y {
+                    *x - (ctx.modulus - *y)
+                } else {
+                    *x + *y
+                }
+            }
+
+            fn montgomery_sub(ctx: &MontgomeryContext<$T>, x: &$T, y: &$T) -> $T {
+                if *x >= *y {
+                    *x - *y
+                } else {
+                    ctx.modulus - (*y - *x)
+                }
+            }
+        }
+    }
+}
+
+montgomery_int!(u32, u64, neg_inv_u32);
+montgomery_int!(u64, u128, neg_inv_u64);
+
+/// A residue held in Montgomery form together with the context it belongs to.
+///
+/// Products, sums, and differences of `MontgomeryForm` values stay in
+/// Montgomery space, so a chain of operations only pays for the conversion
+/// into and out of Montgomery form once.
+///
+/// # Panics
+/// Arithmetic between values built from different contexts panics.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigUint;
+/// use modal::{MontgomeryContext, MontgomeryForm};
+///
+/// fn main() {
+///     let ctx = MontgomeryContext::new(&BigUint::from(29u32)).unwrap();
+///     let x   = MontgomeryForm::new(&BigUint::from(17u32), &ctx);
+///     let y   = MontgomeryForm::new(&BigUint::from(8u32), &ctx);
+///
+///     assert_eq!((&x * &y + &x).residue(), BigUint::from(8u32));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MontgomeryForm<'a, T> where T: 'a {
+    value: T,
+    ctx:   &'a MontgomeryContext<T>,
+}
+
+impl<'a, T> MontgomeryForm<'a, T> where T: MontgomeryInt {
+    /// Converts a value x into Montgomery form, reducing it modulo m.
+    pub fn new(x: &T, ctx: &'a MontgomeryContext<T>) -> MontgomeryForm<'a, T> {
+        MontgomeryForm {
+            value: ctx.to_montgomery(x),
+            ctx,
+        }
+    }
+
+    /// Returns the Montgomery form of one for the context.
+    pub fn one(ctx: &'a MontgomeryContext<T>) -> MontgomeryForm<'a, T> {
+        MontgomeryForm {
+            value: ctx.one(),
+            ctx,
+        }
+    }
+
+    /// Converts the value back to an ordinary residue in ```[0, m)```.
+    pub fn residue(&self) -> T {
+        self.ctx.from_montgomery(&self.value)
+    }
+
+    /// Returns the underlying Montgomery representation ```x * R (mod m)```.
+    pub fn montgomery_value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the context the value belongs to.
+    pub fn context(&self) -> &'a MontgomeryContext<T> {
+        self.ctx
+    }
+
+    #[inline]
+    fn check_context(&self, other: &MontgomeryForm<'a, T>) {
+        assert!(ptr::eq(self.ctx, other.ctx), "Montgomery forms belong to different contexts");
+    }
+}
+
+// Macro for generating the owned and borrowed combinations of a binary
+// operator on MontgomeryForm.
+macro_rules! montgomery_form_op {
+    ( $ Op : ident, $ op : ident, $ ctx_op : ident ) => {
+        impl<'a, 'b, 'c, T> $Op<&'b MontgomeryForm<'a, T>> for &'c MontgomeryForm<'a, T>
+            where T: MontgomeryInt
+        {
+            type Output = MontgomeryForm<'a, T>;
+
+            fn $op(self, rhs: &'b MontgomeryForm<'a, T>) -> MontgomeryForm<'a, T> {
+                self.check_context(rhs);
+
+                MontgomeryForm {
+                    value: self.ctx.$ctx_op(&self.value, &rhs.value),
+                    ctx: self.ctx,
+                }
+            }
+        }
+
+        impl<'a, 'b, T> $Op<&'b MontgomeryForm<'a, T>> for MontgomeryForm<'a, T>
+            where T: MontgomeryInt
+        {
+            type Output = MontgomeryForm<'a, T>;
+
+            fn $op(self, rhs: &'b MontgomeryForm<'a, T>) -> MontgomeryForm<'a, T> {
+                (&self).$op(rhs)
+            }
+        }
+
+        impl<'a, 'c, T> $Op<MontgomeryForm<'a, T>> for &'c MontgomeryForm<'a, T>
+            where T: MontgomeryInt
+        {
+            type Output = MontgomeryForm<'a, T>;
+
+            fn $op(self, rhs: MontgomeryForm<'a, T>) -> MontgomeryForm<'a, T> {
+                self.$op(&rhs)
+            }
+        }
+
+        impl<'a, T> $Op<MontgomeryForm<'a, T>> for MontgomeryForm<'a, T>
+            where T: MontgomeryInt
+        {
+            type Output = MontgomeryForm<'a, T>;
+
+            fn $op(self, rhs: MontgomeryForm<'a, T>) -> MontgomeryForm<'a, T> {
+                (&self).$op(&rhs)
+            }
+        }
+    }
+}
+
+montgomery_form_op!(Add, add, montgomery_add);
+montgomery_form_op!(Sub, sub, montgomery_sub);
+montgomery_form_op!(Mul, mul, montgomery_mult);
+
+#[cfg(test)]
+mod tests {
+    use num::{BigUint, Num, Integer};
+    use error::ModError;
+    use super::{MontgomeryContext, MontgomeryForm};
+
+
+    struct TestCase {
+        x:       BigUint,
+        y:       BigUint,
+        modulus: BigUint,
+    }
+
+    struct Test {
+        data: Vec<TestCase>,
+    }
+
+    fn montgomery_test_cases() -> Test {
+        Test {
+            data: vec![
+                TestCase {
+                    x:       BigUint::from(227791838u32),
+                    y:       BigUint::from(233974561u32),
+                    modulus: BigUint::from(681539081u32),
+                },
+                TestCase {
+                    x:       BigUint::from(2u32),
+                    y:       BigUint::from(3u32),
+                    modulus: BigUint::from(1u32),
+                },
+                TestCase {
+                    x:       <BigUint as Num>::from_str_radix("29424885190633566625547596530635633692994051181214434796327203075", 10).unwrap(),
+                    y:       <BigUint as Num>::from_str_radix("919087970205406919189208074679995123273961", 10).unwrap(),
+                    modulus: <BigUint as Num>::from_str_radix("115792089237316195423570985008687907853269984665640564039457584007908834671663", 10).unwrap(),
+                },
+                TestCase {
+                    x:       <BigUint as Num>::from_str_radix("ffffffffffffffffffffffffffffffff", 16).unwrap(),
+                    y:       <BigUint as Num>::from_str_radix("fffffffffffffffffffffffffffffffe", 16).unwrap(),
+                    modulus: <BigUint as Num>::from_str_radix("100000000000000000000000000000001", 16).unwrap(),
+                }
+            ]
+        }
+    }
+
+    fn run_tests(test: &Test) {
+        for test_case in test.data.iter() {
+            let ctx = MontgomeryContext::new(&test_case.modulus).unwrap();
+            let expected = (&test_case.x * &test_case.y).mod_floor(&test_case.modulus);
+
+            assert_eq!(ctx.mod_mult(&test_case.x, &test_case.y), expected);
+
+            let x_mont = ctx.to_montgomery(&test_case.x);
+            let y_mont = ctx.to_montgomery(&test_case.y);
+            let product = ctx.from_montgomery(&ctx.montgomery_mult(&x_mont, &y_mont));
+
+            assert_eq!(product, expected);
+            assert_eq!(ctx.from_montgomery(&x_mont), test_case.x.mod_floor(&test_case.modulus));
+        }
+    }
+
+    #[test]
+    fn test_montgomery_mult() {
+        run_tests(&montgomery_test_cases());
+    }
+
+    #[test]
+    fn test_montgomery_mod_exp() {
+        let ctx = MontgomeryContext::new(&BigUint::from(833u32)).unwrap();
+
+        assert_eq!(ctx.mod_exp(&BigUint::from(365u32), &915u32), BigUint::from(155u32));
+        assert_eq!(ctx.mod_exp(&BigUint::from(365u32), &0u32), BigUint::from(1u32));
+
+        let ctx = MontgomeryContext::new(&0xffffffffffffffc5u64).unwrap();
+
+        assert_eq!(ctx.mod_exp(&0xfedcba9876543210, &0x123456789abcdefu64), 10464253078750218099);
+    }
+
+    #[test]
+    fn test_montgomery_mod_exp_negative_exponent() {
+        let ctx = MontgomeryContext::new(&BigUint::from(833u32)).unwrap();
+        let x = BigUint::from(365u32);
+
+        assert_eq!(ctx.mod_mult(&ctx.mod_exp(&x, &-915i32), &ctx.mod_exp(&x, &915i32)), BigUint::from(1u32));
+        assert_eq!(ctx.checked_mod_exp(&BigUint::from(14u32), &-1i32), Err(ModError::NotInvertible { gcd: BigUint::from(7u32) }));
+
+        let ctx = MontgomeryContext::new(&0xffffffffffffffc5u64).unwrap();
+
+        assert_eq!(ctx.mod_mult(&ctx.mod_exp(&3, &-5i64), &243), 1);
+    }
+
+    // Test cases for primitive moduli, including moduli close to the radix
+    // where the reduction carries out of the double width product.
+    fn u64_test_cases() -> Vec<(u64, u64, u64)> {
+        vec![
+            (464283712, 559073817, 635717263),
+            (0xfffffffffffffffe, 0xfffffffffffffff0, 0xffffffffffffffc5),
+            (0x7fffffffffffffff, 0x1234567890abcdef, 0xfffffffffffffff1),
+            (1, 0, 1),
+        ]
+    }
+
+    #[test]
+    fn test_montgomery_mult_u64() {
+        for &(x, y, modulus) in u64_test_cases().iter() {
+            let ctx = MontgomeryContext::new(&modulus).unwrap();
+            let expected = ((x as u128 * y as u128) % modulus as u128) as u64;
+
+            assert_eq!(ctx.mod_mult(&(x % modulus), &(y % modulus)), expected);
+        }
+    }
+
+    #[test]
+    fn test_montgomery_mult_u32() {
+        for &(x, y, modulus) in u64_test_cases().iter() {
+            let (x, y, modulus) = (x as u32, y as u32, (modulus as u32) | 1);
+            let ctx = MontgomeryContext::new(&modulus).unwrap();
+            let expected = ((x as u64 * y as u64) % modulus as u64) as u32;
+
+            assert_eq!(ctx.mod_mult(&(x % modulus), &(y % modulus)), expected);
+        }
+    }
+
+    #[test]
+    fn test_montgomery_form_chain() {
+        let modulus = <BigUint as Num>::from_str_radix("115792089237316195423570985008687907853269984665640564039457584007908834671663", 10).unwrap();
+        let ctx = MontgomeryContext::new(&modulus).unwrap();
+        let x = BigUint::from(0xdeadbeefu32);
+        let y = BigUint::from(0x12345678u32);
+
+        let x_mont = MontgomeryForm::new(&x, &ctx);
+        let y_mont = MontgomeryForm::new(&y, &ctx);
+        let mut acc = MontgomeryForm::one(&ctx);
+        let mut expected = BigUint::from(1u32);
+
+        for _ in 0..100 {
+            acc = &acc * &x_mont - &y_mont + &x_mont;
+            expected = ((&expected * &x) + &modulus - &y + &x).mod_floor(&modulus);
+        }
+
+        assert_eq!(acc.residue(), expected);
+    }
+
+    #[test]
+    fn test_montgomery_form_unreduced() {
+        let modulus = BigUint::from(681539081u32);
+        let ctx = MontgomeryContext::new(&modulus).unwrap();
+
+        // Values at or above the modulus, and far above the radix.
+        for x in [&modulus + BigUint::from(5u32), BigUint::from(u32::MAX), &modulus << 200].iter() {
+            let x_mont = MontgomeryForm::new(x, &ctx);
+            let product = &x_mont * &x_mont;
+
+            assert_eq!(x_mont.residue(), x.mod_floor(&modulus));
+            assert_eq!(product.residue(), (x * x).mod_floor(&modulus));
+        }
+    }
+
+    #[test]
+    fn test_montgomery_form_u64() {
+        let modulus: u64 = 0xffffffffffffffc5;
+        let ctx = MontgomeryContext::new(&modulus).unwrap();
+        let x = MontgomeryForm::new(&(modulus - 1), &ctx);
+        let y = MontgomeryForm::new(&3, &ctx);
+
+        assert_eq!((&x + &y).residue(), 2);
+        assert_eq!((&y - &x).residue(), 4);
+        assert_eq!((x * y).residue(), modulus - 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_montgomery_form_context_mismatch() {
+        let ctx1 = MontgomeryContext::new(&29u32).unwrap();
+        let ctx2 = MontgomeryContext::new(&31u32).unwrap();
+
+        let _ = MontgomeryForm::new(&3, &ctx1) * MontgomeryForm::new(&3, &ctx2);
+    }
+
+    #[test]
+    fn test_montgomery_even_modulus() {
+        assert!(MontgomeryContext::new(&BigUint::from(53235608u32)).is_none());
+        assert!(MontgomeryContext::new(&BigUint::from(0u32)).is_none());
+        assert!(MontgomeryContext::new(&1024u64).is_none());
+    }
+}
@@ -0,0 +1,551 @@
+use modadd::ModAdd;
+use modsub::ModSub;
+use modmult::ModMult;
+use modneg::ModNeg;
+use modinv::ModInv;
+use modexp::ModExp;
+use error::ModError;
+use num::{BigInt, Integer, Zero, One, Num};
+use num::traits;
+use std::ops::{Add, Sub, Mul, Div, Rem, Neg};
+use std::ops::{AddAssign, SubAssign, MulAssign, DivAssign, RemAssign};
+use std::iter::{Sum, Product};
+use std::marker::PhantomData;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+use std::fmt;
+
+
+/// The `Modulus` trait supplies a fixed modulus at the type level, for moduli
+/// too large to be const generic parameters.
+///
+/// Implementations typically build the modulus once, on first use, and hand
+/// out a reference to it afterwards.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigInt;
+/// use modal::{Modulus, Residue};
+/// use std::sync::OnceLock;
+///
+/// struct Mersenne127;
+///
+/// impl Modulus for Mersenne127 {
+///     fn modulus() -> &'static BigInt {
+///         static MODULUS: OnceLock<BigInt> = OnceLock::new();
+///
+///         MODULUS.get_or_init(|| (BigInt::from(1) << 127) - 1)
+///     }
+/// }
+///
+/// fn main() {
+///     let x = Residue::<Mersenne127>::new(&(BigInt::from(1) << 100));
+///
+///     assert_eq!(*(&x * &x).value(), BigInt::from(1) << 73);
+/// }
+/// ```
+pub trait Modulus {
+    /// Returns the modulus. It must be positive, and the same on every call.
+    fn modulus() -> &'static BigInt;
+}
+
+// Macro for defining a Modulus from a hexadecimal constant, parsed on first
+// use.
+macro_rules! hex_modulus {
+    ( $ ( # [ $ attr : meta ] ) * $ Name : ident, $ hex : expr ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub struct $Name;
+
+        impl Modulus for $Name {
+            fn modulus() -> &'static BigInt {
+                static MODULUS: OnceLock<BigInt> = OnceLock::new();
+
+                MODULUS.get_or_init(|| <BigInt as Num>::from_str_radix($hex, 16).unwrap())
+            }
+        }
+    }
+}
+
+hex_modulus!(
+    /// The field prime of the NIST P-256 curve,
+    /// ```2 ^ 256 - 2 ^ 224 + 2 ^ 192 + 2 ^ 96 - 1```.
+    P256,
+    "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"
+);
+hex_modulus!(
+    /// The field prime of Curve25519, ```2 ^ 255 - 19```.
+    Curve25519,
+    "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed"
+);
+hex_modulus!(
+    /// The field prime of the secp256k1 curve, ```2 ^ 256 - 2 ^ 32 - 977```.
+    Secp256k1,
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"
+);
+
+/// A residue class of the integers modulo the fixed modulus supplied by `M`.
+///
+/// A `Residue<M>` offers the arithmetic of `Mod<BigInt>` while storing only
+/// its value: the modulus comes from the type, so residues with different
+/// moduli cannot be mixed and no operation has to compare moduli.
+///
+/// # Panics
+/// Division by a residue that has no inverse panics. The `checked_*` methods
+/// return a `ModError` instead.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigInt;
+/// use modal::Residue;
+/// use modal::residue::Curve25519;
+///
+/// fn main() {
+///     let x = Residue::<Curve25519>::new(&BigInt::from(-1));
+///     let y = Residue::<Curve25519>::new(&BigInt::from(2));
+///
+///     assert_eq!(x.value(), &(Residue::<Curve25519>::modulus() - 1));
+///     assert_eq!(*(&x + &y).value(), BigInt::from(1));
+///     assert_eq!(&(&x / &y) * &y, x);
+/// }
+/// ```
+pub struct Residue<M: Modulus> {
+    value: BigInt,
+    // fn() -> M keeps Residue Send and Sync whatever the marker type is.
+    modulus: PhantomData<fn() -> M>,
+}
+
+impl<M: Modulus> Residue<M> {
+    // Wraps a value already reduced into [0, m).
+    #[inline]
+    fn from_reduced(value: BigInt) -> Residue<M> {
+        Residue {
+            value,
+            modulus: PhantomData,
+        }
+    }
+
+    /// Constructs the residue class of `value`. The value is reduced into the
+    /// canonical range ```[0, m)``` first.
+    pub fn new(value: &BigInt) -> Residue<M> {
+        Residue::from_reduced(value.mod_floor(M::modulus()))
+    }
+
+    /// Returns the canonical residue in ```[0, m)```.
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    /// Returns the modulus of the residue type.
+    pub fn modulus() -> &'static BigInt {
+        M::modulus()
+    }
+
+    /// Consumes the residue class, returning its canonical residue.
+    pub fn un_mod(self) -> BigInt {
+        self.value
+    }
+
+    /// Returns the residue class of zero.
+    pub fn zero() -> Residue<M> {
+        Residue::from_reduced(Zero::zero())
+    }
+
+    /// Returns the residue class of one.
+    pub fn one() -> Residue<M> {
+        Residue::new(&One::one())
+    }
+
+    /// Computes the multiplicative inverse of the residue class.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the value is not invertible modulo the modulus.
+    pub fn inv(&self) -> Option<Residue<M>> {
+        self.value.mod_inv(M::modulus()).map(Residue::from_reduced)
+    }
+
+    /// Computes the multiplicative inverse of the residue class, returning an
+    /// error that carries the common factor when there is none.
+    pub fn checked_inv(&self) -> Result<Residue<M>, ModError<BigInt>> {
+        self.inv().ok_or_else(|| {
+            ModError::NotInvertible {
+                gcd: self.value.gcd(M::modulus()),
+            }
+        })
+    }
+
+    /// Computes ```self / rhs == self * rhs ^ -1```, returning an error instead
+    /// of panicking when rhs is not invertible.
+    pub fn checked_div(&self, rhs: &Residue<M>) -> Result<Residue<M>, ModError<BigInt>> {
+        rhs.checked_inv().map(|rhs_inv| self * &rhs_inv)
+    }
+
+    /// Computes ```self ^ exponent```. A negative exponent raises the inverse
+    /// to ```|exponent|```.
+    ///
+    /// # Panics
+    /// when the exponent is negative and the residue is not invertible.
+    pub fn pow(&self, exponent: &BigInt) -> Residue<M> {
+        self.checked_pow(exponent).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Computes ```self ^ exponent```, returning an error instead of panicking
+    /// when the exponent is negative and the residue is not invertible.
+    pub fn checked_pow(&self, exponent: &BigInt) -> Result<Residue<M>, ModError<BigInt>> {
+        Ok(Residue::from_reduced(self.value.checked_mod_exp(exponent, M::modulus())?))
+    }
+}
+
+impl<M: Modulus> Clone for Residue<M> {
+    fn clone(&self) -> Residue<M> {
+        Residue::from_reduced(self.value.clone())
+    }
+}
+
+impl<M: Modulus> fmt::Debug for Residue<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Residue")
+            .field("value", &self.value)
+            .field("modulus", M::modulus())
+            .finish()
+    }
+}
+
+impl<M: Modulus> PartialEq for Residue<M> {
+    fn eq(&self, other: &Residue<M>) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<M: Modulus> Eq for Residue<M> {}
+
+impl<M: Modulus> Hash for Residue<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl<M: Modulus> Default for Residue<M> {
+    fn default() -> Residue<M> {
+        Residue::zero()
+    }
+}
+
+impl<'a, M: Modulus> AddAssign<&'a Residue<M>> for Residue<M> {
+    fn add_assign(&mut self, rhs: &'a Residue<M>) {
+        self.value = self.value.mod_add(&rhs.value, M::modulus());
+    }
+}
+
+impl<'a, M: Modulus> SubAssign<&'a Residue<M>> for Residue<M> {
+    fn sub_assign(&mut self, rhs: &'a Residue<M>) {
+        self.value = self.value.mod_sub(&rhs.value, M::modulus());
+    }
+}
+
+impl<'a, M: Modulus> MulAssign<&'a Residue<M>> for Residue<M> {
+    fn mul_assign(&mut self, rhs: &'a Residue<M>) {
+        self.value = self.value.mod_mult(&rhs.value, M::modulus());
+    }
+}
+
+impl<'a, M: Modulus> DivAssign<&'a Residue<M>> for Residue<M> {
+    fn div_assign(&mut self, rhs: &'a Residue<M>) {
+        let rhs_inv = rhs.checked_inv().unwrap_or_else(|err| panic!("{}", err));
+
+        self.value = self.value.mod_mult(&rhs_inv.value, M::modulus());
+    }
+}
+
+// Every invertible residue divides every residue exactly, so the remainder of
+// a division is always zero. Like division, it panics when rhs is not
+// invertible.
+impl<'a, M: Modulus> RemAssign<&'a Residue<M>> for Residue<M> {
+    fn rem_assign(&mut self, rhs: &'a Residue<M>) {
+        rhs.checked_inv().unwrap_or_else(|err| panic!("{}", err));
+        self.value.set_zero();
+    }
+}
+
+// Macro for generating every owned and borrowed combination of a binary
+// operator on Residue, and the owned compound assignment, from the compound
+// assignment by reference. An owned left operand is updated in place.
+macro_rules! residue_binop {
+    ( impl $ Op : ident, $ op : ident, impl $ OpAssign : ident, $ op_assign : ident ) => {
+        impl<'a, M: Modulus> $Op<&'a Residue<M>> for &Residue<M> {
+            type Output = Residue<M>;
+
+            fn $op(self, rhs: &'a Residue<M>) -> Residue<M> {
+                self.clone().$op(rhs)
+            }
+        }
+
+        impl<M: Modulus> $Op<Residue<M>> for Residue<M> {
+            type Output = Residue<M>;
+
+            fn $op(mut self, rhs: Residue<M>) -> Residue<M> {
+                self.$op_assign(&rhs);
+                self
+            }
+        }
+
+        impl<'a, M: Modulus> $Op<&'a Residue<M>> for Residue<M> {
+            type Output = Residue<M>;
+
+            fn $op(mut self, rhs: &'a Residue<M>) -> Residue<M> {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl<M: Modulus> $Op<Residue<M>> for &Residue<M> {
+            type Output = Residue<M>;
+
+            fn $op(self, rhs: Residue<M>) -> Residue<M> {
+                self.$op(&rhs)
+            }
+        }
+
+        impl<M: Modulus> $OpAssign<Residue<M>> for Residue<M> {
+            fn $op_assign(&mut self, rhs: Residue<M>) {
+                self.$op_assign(&rhs)
+            }
+        }
+    }
+}
+
+residue_binop!(impl Add, add, impl AddAssign, add_assign);
+residue_binop!(impl Sub, sub, impl SubAssign, sub_assign);
+residue_binop!(impl Mul, mul, impl MulAssign, mul_assign);
+residue_binop!(impl Div, div, impl DivAssign, div_assign);
+residue_binop!(impl Rem, rem, impl RemAssign, rem_assign);
+
+impl<M: Modulus> Neg for Residue<M> {
+    type Output = Residue<M>;
+
+    fn neg(self) -> Residue<M> {
+        -&self
+    }
+}
+
+impl<M: Modulus> Neg for &Residue<M> {
+    type Output = Residue<M>;
+
+    fn neg(self) -> Residue<M> {
+        Residue::from_reduced(self.value.mod_neg(M::modulus()))
+    }
+}
+
+impl<M: Modulus> Sum for Residue<M> {
+    fn sum<It: Iterator<Item=Residue<M>>>(iter: It) -> Residue<M> {
+        iter.fold(Residue::zero(), |acc, x| acc + x)
+    }
+}
+
+impl<'a, M: Modulus> Sum<&'a Residue<M>> for Residue<M> {
+    fn sum<It: Iterator<Item=&'a Residue<M>>>(iter: It) -> Residue<M> {
+        iter.fold(Residue::zero(), |acc, x| acc + x)
+    }
+}
+
+impl<M: Modulus> Product for Residue<M> {
+    fn product<It: Iterator<Item=Residue<M>>>(iter: It) -> Residue<M> {
+        iter.fold(Residue::one(), |acc, x| acc * x)
+    }
+}
+
+impl<'a, M: Modulus> Product<&'a Residue<M>> for Residue<M> {
+    fn product<It: Iterator<Item=&'a Residue<M>>>(iter: It) -> Residue<M> {
+        iter.fold(Residue::one(), |acc, x| acc * x)
+    }
+}
+
+
+impl<M: Modulus> traits::Zero for Residue<M> {
+    fn zero() -> Residue<M> {
+        Residue::zero()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+}
+
+impl<M: Modulus> traits::One for Residue<M> {
+    fn one() -> Residue<M> {
+        Residue::one()
+    }
+}
+
+impl<M: Modulus> traits::Num for Residue<M> {
+    type FromStrRadixErr = <BigInt as Num>::FromStrRadixErr;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Residue<M>, Self::FromStrRadixErr> {
+        <BigInt as Num>::from_str_radix(s, radix).map(|value| Residue::new(&value))
+    }
+}
+
+// Macro for generating the num Pow implementations for owned and borrowed
+// residues and exponents, following the inherent `pow`.
+macro_rules! residue_pow {
+    ( $ ( $ Lhs : ty , $ Rhs : ty ) ; + ) => {
+        $(
+            impl<'a, 'b, M: Modulus> traits::Pow<$Rhs> for $Lhs {
+                type Output = Residue<M>;
+
+                fn pow(self, exponent: $Rhs) -> Residue<M> {
+                    Residue::pow(&self, &exponent)
+                }
+            }
+        )+
+    }
+}
+
+residue_pow!(Residue<M>, BigInt; Residue<M>, &'a BigInt; &'b Residue<M>, BigInt; &'b Residue<M>, &'a BigInt);
+
+impl<M: Modulus> traits::Inv for Residue<M> {
+    type Output = Residue<M>;
+
+    fn inv(self) -> Residue<M> {
+        traits::Inv::inv(&self)
+    }
+}
+
+impl<M: Modulus> traits::Inv for &Residue<M> {
+    type Output = Residue<M>;
+
+    fn inv(self) -> Residue<M> {
+        self.checked_inv().unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl<M: Modulus> traits::CheckedDiv for Residue<M> {
+    fn checked_div(&self, rhs: &Residue<M>) -> Option<Residue<M>> {
+        Residue::checked_div(self, rhs).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num;
+    use num::{BigInt, Num, One};
+    use num::traits;
+    use error::ModError;
+    use super::{Modulus, Residue, P256, Curve25519, Secp256k1};
+
+
+    struct Twelve;
+
+    impl Modulus for Twelve {
+        fn modulus() -> &'static BigInt {
+            use std::sync::OnceLock;
+            static MODULUS: OnceLock<BigInt> = OnceLock::new();
+
+            MODULUS.get_or_init(|| BigInt::from(12))
+        }
+    }
+
+    fn hex(digits: &str) -> BigInt {
+        <BigInt as Num>::from_str_radix(digits, 16).unwrap()
+    }
+
+    #[test]
+    fn test_residue_moduli() {
+        let one = BigInt::one();
+
+        assert_eq!(*P256::modulus(), (&one << 256) - (&one << 224) + (&one << 192) + (&one << 96) - 1);
+        assert_eq!(*Curve25519::modulus(), (&one << 255) - 19);
+        assert_eq!(*Secp256k1::modulus(), (&one << 256) - (&one << 32) - 977);
+
+        // Fermat's little theorem holds for each of the primes.
+        let three = Residue::<P256>::new(&BigInt::from(3));
+        assert_eq!(three.pow(&(P256::modulus() - 1)), Residue::one());
+    }
+
+    #[test]
+    fn test_residue_secp256k1_generator() {
+        type F = Residue<Secp256k1>;
+
+        let x = F::new(&hex("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"));
+        let y = F::new(&hex("483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"));
+        let seven = F::new(&BigInt::from(7));
+
+        // The generator lies on y^2 == x^3 + 7.
+        assert_eq!(&y * &y, &(&x * &x) * &x + seven);
+    }
+
+    #[test]
+    fn test_residue_curve25519_sqrt_minus_one() {
+        type F = Residue<Curve25519>;
+
+        let exponent = (Curve25519::modulus() - 1) / 4;
+        let i = F::new(&BigInt::from(2)).pow(&exponent);
+
+        assert_eq!(&i * &i, -F::one());
+        assert_eq!(i.pow(&BigInt::from(-4)), F::one());
+    }
+
+    #[test]
+    fn test_residue_operators() {
+        type R = Residue<Twelve>;
+
+        let x = R::new(&BigInt::from(-7));
+        let y = R::new(&BigInt::from(7));
+        let mut z = x.clone();
+
+        assert_eq!(*x.value(), BigInt::from(5));
+        assert_eq!(*(&x + &y).value(), BigInt::from(0));
+        assert_eq!(*(x.clone() - &y).value(), BigInt::from(10));
+        assert_eq!(*(&x * y.clone()).value(), BigInt::from(11));
+        assert_eq!(*(x.clone() / y.clone()).value(), BigInt::from(11));
+        assert_eq!(*(-&x).value(), BigInt::from(7));
+
+        z *= &y;
+        z += y.clone();
+        assert_eq!(*z.value(), BigInt::from(6));
+        z /= &x;
+        assert_eq!(*z.value(), BigInt::from(6));
+
+        assert_eq!(R::new(&BigInt::from(8)).checked_inv(), Err(ModError::NotInvertible { gcd: BigInt::from(4) }));
+        assert_eq!((1..5).map(|x| R::new(&BigInt::from(x))).sum::<R>(), R::new(&BigInt::from(10)));
+        assert_eq!((1..5).map(|x| R::new(&BigInt::from(x))).product::<R>(), R::new(&BigInt::from(0)));
+    }
+
+    #[test]
+    fn test_residue_num_traits() {
+        type F = Residue<P256>;
+
+        // Generic code written against the num traits runs over the field.
+        fn sum_of_powers<T: traits::Num + Clone>(x: T, n: usize) -> T {
+            (0..n).fold(T::zero(), |acc, k| acc + num::pow(x.clone(), k))
+        }
+
+        let x = F::new(&BigInt::from(3));
+
+        // 1 + 3 + 9 + 27 == 40.
+        assert_eq!(sum_of_powers(x.clone(), 4), F::new(&BigInt::from(40)));
+        assert_eq!(<F as traits::Num>::from_str_radix("-1", 10), Ok(-F::one()));
+        assert_eq!(traits::Pow::pow(&x, BigInt::from(-1)), traits::Inv::inv(&x));
+        assert_eq!(traits::Inv::inv(x.clone()) * &x, F::one());
+        assert_eq!(&x % &x, F::zero());
+
+        type R = Residue<Twelve>;
+
+        assert_eq!(traits::CheckedDiv::checked_div(&R::one(), &R::new(&BigInt::from(8))), None);
+        assert!(traits::One::is_one(&R::new(&BigInt::from(13))));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_residue_div_non_invertible() {
+        let _ = Residue::<Twelve>::one() / Residue::new(&BigInt::from(3));
+    }
+}
@@ -0,0 +1,556 @@
+use montgomery::MontgomeryContext;
+use barrett::BarrettContext;
+use modinv::ModInv;
+use modexp::sliding_window_exp;
+use error::ModError;
+use num::{BigInt, BigUint, Integer, Zero, One};
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::ops::{AddAssign, SubAssign, MulAssign, DivAssign};
+use std::sync::Arc;
+use std::fmt;
+
+
+// The reduction strategy for a modulus. Odd moduli keep residues in
+// Montgomery form; even moduli keep ordinary residues and use Barrett
+// reduction.
+#[derive(Clone, Debug)]
+enum Reducer {
+    Montgomery(MontgomeryContext<BigUint>),
+    Barrett(BarrettContext),
+}
+
+/// A modulus together with its precomputed reduction constants, meant to be
+/// shared between many `SharedMod` residues through an `Arc`.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate num;
+/// extern crate modal;
+///
+/// use num::BigUint;
+/// use modal::ModContext;
+/// use std::sync::Arc;
+///
+/// fn main() {
+///     let ctx = Arc::new(ModContext::new(&BigUint::from(29u32)).unwrap());
+///     let x   = ctx.residue(&BigUint::from(17u32));
+///     let y   = ctx.residue(&BigUint::from(21u32));
+///
+///     assert_eq!((&x * &y + &x).value(), BigUint::from(26u32));
+///     assert_eq!((&x / &y * &y), x);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ModContext {
+    modulus: BigUint,
+    reducer: Reducer,
+}
+
+impl ModContext {
+    /// Constructs a context for the modulus m, choosing Montgomery reduction
+    /// for odd moduli and Barrett reduction for even ones.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the modulus is zero.
+    pub fn new(modulus: &BigUint) -> Option<ModContext> {
+        if modulus.is_zero() {
+            return None;
+        }
+
+        let reducer = if modulus.is_odd() && !modulus.is_one() {
+            Reducer::Montgomery(MontgomeryContext::new(modulus)?)
+        } else {
+            Reducer::Barrett(BarrettContext::new(modulus)?)
+        };
+
+        Some(ModContext {
+            modulus: modulus.clone(),
+            reducer,
+        })
+    }
+
+    /// Returns the modulus of the context.
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    /// Constructs the residue class of `value` sharing this context.
+    pub fn residue(self: &Arc<Self>, value: &BigUint) -> SharedMod {
+        SharedMod::new(value, self)
+    }
+
+    // Maps any value to the internal representation of its residue.
+    #[inline]
+    fn encode(&self, x: &BigUint) -> BigUint {
+        match self.reducer {
+            Reducer::Montgomery(ref ctx) => {
+                if *x < self.modulus {
+                    ctx.to_montgomery(x)
+                } else {
+                    ctx.to_montgomery(&x.mod_floor(&self.modulus))
+                }
+            }
+            Reducer::Barrett(ref ctx) => ctx.reduce(x),
+        }
+    }
+
+    // Maps an internal representation back to the residue in [0, m).
+    #[inline]
+    fn decode(&self, x: &BigUint) -> BigUint {
+        match self.reducer {
+            Reducer::Montgomery(ref ctx) => ctx.from_montgomery(x),
+            Reducer::Barrett(_) => x.clone(),
+        }
+    }
+
+    // Adds two internal representations. Both are below m in either
+    // representation, so one conditional subtraction reduces the sum.
+    #[inline]
+    fn add_repr(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        match self.reducer {
+            Reducer::Montgomery(ref ctx) => ctx.montgomery_add(x, y),
+            Reducer::Barrett(_) => {
+                let sum = x + y;
+
+                if sum >= self.modulus {
+                    sum - &self.modulus
+                } else {
+                    sum
+                }
+            }
+        }
+    }
+
+    // Subtracts two internal representations, adding m back on underflow.
+    #[inline]
+    fn sub_repr(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        match self.reducer {
+            Reducer::Montgomery(ref ctx) => ctx.montgomery_sub(x, y),
+            Reducer::Barrett(_) => {
+                if x >= y {
+                    x - y
+                } else {
+                    &self.modulus - (y - x)
+                }
+            }
+        }
+    }
+
+    // Multiplies two internal representations.
+    #[inline]
+    fn mul_repr(&self, x: &BigUint, y: &BigUint) -> BigUint {
+        match self.reducer {
+            Reducer::Montgomery(ref ctx) => ctx.montgomery_mult(x, y),
+            Reducer::Barrett(ref ctx) => ctx.reduce(&(x * y)),
+        }
+    }
+
+    // The internal representation of one.
+    #[inline]
+    fn one_repr(&self) -> BigUint {
+        match self.reducer {
+            Reducer::Montgomery(ref ctx) => ctx.one(),
+            Reducer::Barrett(ref ctx) => ctx.reduce(&One::one()),
+        }
+    }
+}
+
+/// A residue class modulo a modulus held in a shared `ModContext`.
+///
+/// Where `Mod<I>` clones its modulus into every value, a `SharedMod` holds an
+/// `Arc` to its context, so creating a residue costs one reference count
+/// increment. Residues of the same context are checked for compatibility by
+/// pointer comparison, falling back to comparing moduli for residues built
+/// from distinct contexts. Odd moduli keep values in Montgomery form, so a
+/// product costs a single Montgomery multiplication.
+///
+/// # Panics
+/// Arithmetic between residues with different moduli panics, as does
+/// division by a residue that has no inverse. The `checked_*` methods are
+/// non-panicking alternatives that return a `ModError` instead.
+#[derive(Clone)]
+pub struct SharedMod {
+    repr:    BigUint,
+    context: Arc<ModContext>,
+}
+
+impl SharedMod {
+    /// Constructs the residue class of `value` in the context.
+    pub fn new(value: &BigUint, context: &Arc<ModContext>) -> SharedMod {
+        SharedMod {
+            repr: context.encode(value),
+            context: context.clone(),
+        }
+    }
+
+    /// Constructs the residue class of a signed `value` in the context.
+    pub fn from_bigint(value: &BigInt, context: &Arc<ModContext>) -> SharedMod {
+        let modulus = BigInt::from(context.modulus.clone());
+        let value = value.mod_floor(&modulus).to_biguint().unwrap();
+
+        SharedMod::new(&value, context)
+    }
+
+    /// Returns the residue class of zero in the context.
+    pub fn zero(context: &Arc<ModContext>) -> SharedMod {
+        SharedMod {
+            repr: Zero::zero(),
+            context: context.clone(),
+        }
+    }
+
+    /// Returns the residue class of one in the context.
+    pub fn one(context: &Arc<ModContext>) -> SharedMod {
+        SharedMod {
+            repr: context.one_repr(),
+            context: context.clone(),
+        }
+    }
+
+    /// Returns the canonical residue in ```[0, m)```.
+    pub fn value(&self) -> BigUint {
+        self.context.decode(&self.repr)
+    }
+
+    /// Returns the modulus.
+    pub fn modulus(&self) -> &BigUint {
+        &self.context.modulus
+    }
+
+    /// Returns the shared context.
+    pub fn context(&self) -> &Arc<ModContext> {
+        &self.context
+    }
+
+    // Checks that two residues share a modulus, comparing the contexts by
+    // address before comparing moduli.
+    #[inline]
+    fn check_context(&self, rhs: &SharedMod) -> Result<(), ModError<BigUint>> {
+        if Arc::ptr_eq(&self.context, &rhs.context) || self.context.modulus == rhs.context.modulus {
+            Ok(())
+        } else {
+            Err(ModError::ModulusMismatch {
+                left: self.context.modulus.clone(),
+                right: rhs.context.modulus.clone(),
+            })
+        }
+    }
+
+    // Wraps a representation in this residue's context.
+    #[inline]
+    fn with_repr(&self, repr: BigUint) -> SharedMod {
+        SharedMod {
+            repr,
+            context: self.context.clone(),
+        }
+    }
+
+    /// Computes ```self + rhs```, returning an error instead of panicking when
+    /// the moduli differ.
+    pub fn checked_add(&self, rhs: &SharedMod) -> Result<SharedMod, ModError<BigUint>> {
+        self.check_context(rhs)?;
+
+        Ok(self.with_repr(self.context.add_repr(&self.repr, &rhs.repr)))
+    }
+
+    /// Computes ```self - rhs```, returning an error instead of panicking when
+    /// the moduli differ.
+    pub fn checked_sub(&self, rhs: &SharedMod) -> Result<SharedMod, ModError<BigUint>> {
+        self.check_context(rhs)?;
+
+        Ok(self.with_repr(self.context.sub_repr(&self.repr, &rhs.repr)))
+    }
+
+    /// Computes ```self * rhs```, returning an error instead of panicking when
+    /// the moduli differ.
+    pub fn checked_mul(&self, rhs: &SharedMod) -> Result<SharedMod, ModError<BigUint>> {
+        self.check_context(rhs)?;
+
+        Ok(self.with_repr(self.context.mul_repr(&self.repr, &rhs.repr)))
+    }
+
+    /// Computes ```self / rhs == self * rhs ^ -1```, returning an error instead
+    /// of panicking when the moduli differ or rhs is not invertible.
+    pub fn checked_div(&self, rhs: &SharedMod) -> Result<SharedMod, ModError<BigUint>> {
+        self.check_context(rhs)?;
+
+        let rhs_inv = rhs.checked_inv()?;
+
+        Ok(self.with_repr(self.context.mul_repr(&self.repr, &rhs_inv.repr)))
+    }
+
+    // In place versions of the checked methods, used by the compound
+    // assignment operators.
+    fn checked_add_assign(&mut self, rhs: &SharedMod) -> Result<(), ModError<BigUint>> {
+        self.check_context(rhs)?;
+        self.repr = self.context.add_repr(&self.repr, &rhs.repr);
+
+        Ok(())
+    }
+
+    fn checked_sub_assign(&mut self, rhs: &SharedMod) -> Result<(), ModError<BigUint>> {
+        self.check_context(rhs)?;
+        self.repr = self.context.sub_repr(&self.repr, &rhs.repr);
+
+        Ok(())
+    }
+
+    fn checked_mul_assign(&mut self, rhs: &SharedMod) -> Result<(), ModError<BigUint>> {
+        self.check_context(rhs)?;
+        self.repr = self.context.mul_repr(&self.repr, &rhs.repr);
+
+        Ok(())
+    }
+
+    fn checked_div_assign(&mut self, rhs: &SharedMod) -> Result<(), ModError<BigUint>> {
+        self.check_context(rhs)?;
+
+        let rhs_inv = rhs.checked_inv()?;
+        self.repr = self.context.mul_repr(&self.repr, &rhs_inv.repr);
+
+        Ok(())
+    }
+
+    /// Computes the multiplicative inverse of the residue class.
+    ///
+    /// # Errors
+    /// Returns ```None``` when the value is not invertible modulo the modulus.
+    pub fn inv(&self) -> Option<SharedMod> {
+        self.value().mod_inv(&self.context.modulus).map(|val| {
+            self.with_repr(self.context.encode(&val))
+        })
+    }
+
+    /// Computes the multiplicative inverse of the residue class, returning an
+    /// error that carries the common factor when there is none.
+    pub fn checked_inv(&self) -> Result<SharedMod, ModError<BigUint>> {
+        let value = self.value();
+
+        match value.mod_inv(&self.context.modulus) {
+            Some(val) => Ok(self.with_repr(self.context.encode(&val))),
+            None => Err(ModError::NotInvertible { gcd: value.gcd(&self.context.modulus) }),
+        }
+    }
+
+    /// Computes ```self ^ exponent``` by sliding window exponentiation, without
+    /// leaving the internal representation.
+    pub fn pow(&self, exponent: &BigUint) -> SharedMod {
+        let context = &self.context;
+        let result = sliding_window_exp(context.one_repr(), self.repr.clone(), exponent, |x, y| context.mul_repr(x, y));
+
+        self.with_repr(result)
+    }
+}
+
+impl fmt::Debug for SharedMod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SharedMod")
+            .field("value", &self.value())
+            .field("modulus", &self.context.modulus)
+            .finish()
+    }
+}
+
+// Residues with equal moduli have equal representations exactly when their
+// values agree, whichever context they were built from.
+impl PartialEq for SharedMod {
+    fn eq(&self, other: &SharedMod) -> bool {
+        self.check_context(other).is_ok() && self.repr == other.repr
+    }
+}
+
+impl Eq for SharedMod {}
+
+// Macro for generating every owned and borrowed combination of a binary
+// operator on SharedMod, along with its compound assignment operator. All of
+// them defer to the checked methods, and panic with their error. An owned
+// left operand is updated in place and returned.
+macro_rules! shared_binop {
+    ( impl $ Op : ident, $ op : ident, $ checked : ident,
+      impl $ OpAssign : ident, $ op_assign : ident, $ checked_assign : ident ) => {
+        impl<'a> $Op<&'a SharedMod> for &SharedMod {
+            type Output = SharedMod;
+
+            fn $op(self, rhs: &'a SharedMod) -> SharedMod {
+                self.$checked(rhs).unwrap_or_else(|err| panic!("{}", err))
+            }
+        }
+
+        impl $Op<SharedMod> for SharedMod {
+            type Output = SharedMod;
+
+            fn $op(mut self, rhs: SharedMod) -> SharedMod {
+                self.$op_assign(&rhs);
+                self
+            }
+        }
+
+        impl<'a> $Op<&'a SharedMod> for SharedMod {
+            type Output = SharedMod;
+
+            fn $op(mut self, rhs: &'a SharedMod) -> SharedMod {
+                self.$op_assign(rhs);
+                self
+            }
+        }
+
+        impl $Op<SharedMod> for &SharedMod {
+            type Output = SharedMod;
+
+            fn $op(self, rhs: SharedMod) -> SharedMod {
+                self.$op(&rhs)
+            }
+        }
+
+        impl<'a> $OpAssign<&'a SharedMod> for SharedMod {
+            fn $op_assign(&mut self, rhs: &'a SharedMod) {
+                self.$checked_assign(rhs).unwrap_or_else(|err| panic!("{}", err))
+            }
+        }
+
+        impl $OpAssign<SharedMod> for SharedMod {
+            fn $op_assign(&mut self, rhs: SharedMod) {
+                self.$op_assign(&rhs)
+            }
+        }
+    }
+}
+
+shared_binop!(impl Add, add, checked_add, impl AddAssign, add_assign, checked_add_assign);
+shared_binop!(impl Sub, sub, checked_sub, impl SubAssign, sub_assign, checked_sub_assign);
+shared_binop!(impl Mul, mul, checked_mul, impl MulAssign, mul_assign, checked_mul_assign);
+shared_binop!(impl Div, div, checked_div, impl DivAssign, div_assign, checked_div_assign);
+
+impl Neg for SharedMod {
+    type Output = SharedMod;
+
+    fn neg(self) -> SharedMod {
+        -&self
+    }
+}
+
+impl Neg for &SharedMod {
+    type Output = SharedMod;
+
+    fn neg(self) -> SharedMod {
+        self.with_repr(self.context.sub_repr(&BigUint::zero(), &self.repr))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use num::{BigInt, BigUint, One};
+    use error::ModError;
+    use modinv::ModInv;
+    use super::{ModContext, SharedMod};
+    use std::sync::Arc;
+
+
+    fn context(modulus: &BigUint) -> Arc<ModContext> {
+        Arc::new(ModContext::new(modulus).unwrap())
+    }
+
+    // Checks the arithmetic against plain BigUint operations for a sample of
+    // values modulo m.
+    fn check_arithmetic(modulus: &BigUint) {
+        let ctx = context(modulus);
+        let samples = [
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(0xdeadbeefu32),
+            modulus - 1u32,
+            modulus * 3u32 + 5u32,
+            (modulus >> 1) + 7u32,
+        ];
+
+        for a in samples.iter() {
+            for b in samples.iter() {
+                let x = ctx.residue(a);
+                let y = ctx.residue(b);
+                let (a, b) = (a % modulus, b % modulus);
+
+                assert_eq!(x.value(), a);
+                assert_eq!((&x + &y).value(), (&a + &b) % modulus);
+                assert_eq!((&x - &y).value(), (&a + modulus - &b) % modulus);
+                assert_eq!((&x * &y).value(), (&a * &b) % modulus);
+                assert_eq!((-&x).value(), (modulus - &a) % modulus);
+
+                match b.mod_inv(modulus) {
+                    Some(b_inv) => assert_eq!((&x / &y).value(), (&a * b_inv) % modulus),
+                    None => assert!(x.checked_div(&y).is_err()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_mod_arithmetic() {
+        let one = BigUint::one();
+
+        check_arithmetic(&((&one << 521) - 1u32));
+        check_arithmetic(&((&one << 300) - 2u32));
+        check_arithmetic(&BigUint::from(29u32));
+        check_arithmetic(&BigUint::from(1u32));
+    }
+
+    #[test]
+    fn test_shared_mod_pow_inv() {
+        let p = (BigUint::one() << 127) - 1u32;
+        let ctx = context(&p);
+        let x = ctx.residue(&(BigUint::one() << 100));
+
+        assert_eq!(x.pow(&BigUint::from(2u32)).value(), BigUint::one() << 73);
+        assert_eq!(x.pow(&(&p - 1u32)), SharedMod::one(&ctx));
+        assert_eq!(&x.inv().unwrap() * &x, SharedMod::one(&ctx));
+
+        let ctx = context(&BigUint::from(30u32));
+        let y = ctx.residue(&BigUint::from(12u32));
+
+        assert_eq!(y.checked_inv().unwrap_err(), ModError::NotInvertible { gcd: BigUint::from(6u32) });
+        assert_eq!(y.pow(&BigUint::from(3u32)).value(), BigUint::from(18u32));
+        assert_eq!(SharedMod::from_bigint(&BigInt::from(-7), &ctx).value(), BigUint::from(23u32));
+    }
+
+    #[test]
+    fn test_shared_mod_contexts() {
+        let modulus = BigUint::from(1000003u32);
+        let ctx = context(&modulus);
+        let other = context(&modulus);
+        let x = ctx.residue(&BigUint::from(5u32));
+        let y = other.residue(&BigUint::from(5u32));
+
+        // Equal moduli from distinct contexts are compatible.
+        assert_eq!(x, y);
+        assert_eq!((&x * &y).value(), BigUint::from(25u32));
+
+        let mut z = x.clone();
+        z *= &y;
+        z += y.clone();
+        z -= &x;
+        z /= y;
+
+        assert_eq!(z, x);
+        assert!(Arc::ptr_eq(z.context(), &ctx));
+        assert_eq!(Arc::strong_count(&ctx), 3);
+
+        let w = context(&BigUint::from(7u32)).residue(&BigUint::from(5u32));
+
+        assert!(x != w);
+        assert_eq!(x.checked_add(&w).unwrap_err(), ModError::ModulusMismatch {
+            left: modulus,
+            right: BigUint::from(7u32),
+        });
+        assert!(ModContext::new(&BigUint::from(0u32)).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shared_mod_modulus_mismatch() {
+        let x = context(&BigUint::from(7u32)).residue(&BigUint::from(3u32));
+        let y = context(&BigUint::from(11u32)).residue(&BigUint::from(3u32));
+
+        let _ = x + y;
+    }
+}
@@ -0,0 +1,25 @@
+// Helpers shared by the tests of several modules.
+use num::BigInt;
+
+
+// Checks a binary modular operation on i64 against the BigInt implementation,
+// over values and moduli of both signs including the extremes of the type,
+// where the primitive implementations must widen or reorder their arithmetic
+// to avoid overflow.
+pub(crate) fn check_signed_matches_bigint<F, G>(op: F, bigint_op: G)
+    where F: Fn(i64, i64, i64) -> i64,
+          G: Fn(&BigInt, &BigInt, &BigInt) -> BigInt
+{
+    let values: [i64; 8] = [i64::MIN, -735172765, -446253906, -1, 0, 1, 446253906, i64::MAX];
+    let moduli: [i64; 5] = [53235608, 681539081, -538744916, i64::MAX, i64::MIN];
+
+    for &x in values.iter() {
+        for &y in values.iter() {
+            for &modulus in moduli.iter() {
+                let expected = bigint_op(&BigInt::from(x), &BigInt::from(y), &BigInt::from(modulus));
+
+                assert_eq!(BigInt::from(op(x, y, modulus)), expected, "x = {}, y = {}, modulus = {}", x, y, modulus);
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Double width arithmetic for the primitive types. A product of two values is
+// returned as a (high, low) pair of words, which lets u128 moduli use 256 bit
+// intermediate products without a wider primitive.
+
+
+// Computes the full double width product of x and y as a (high, low) pair.
+#[inline]
+pub(crate) const fn mul_wide_u32(x: u32, y: u32) -> (u32, u32) {
+    let product = (x as u64) * (y as u64);
+
+    ((product >> 32) as u32, product as u32)
+}
+
+#[inline]
+pub(crate) const fn mul_wide_u64(x: u64, y: u64) -> (u64, u64) {
+    let product = (x as u128) * (y as u128);
+
+    ((product >> 64) as u64, product as u64)
+}
+
+// There is no primitive wider than u128, so the product is assembled from
+// four 64 bit partial products.
+#[inline]
+pub(crate) const fn mul_wide_u128(x: u128, y: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+
+    let (x_hi, x_lo) = (x >> 64, x & MASK);
+    let (y_hi, y_lo) = (y >> 64, y & MASK);
+
+    let lo_lo = x_lo * y_lo;
+    let lo_hi = x_lo * y_hi;
+    let hi_lo = x_hi * y_lo;
+    let hi_hi = x_hi * y_hi;
+
+    // At most three 64 bit terms, so the middle column cannot overflow.
+    let middle = (lo_lo >> 64) + (lo_hi & MASK) + (hi_lo & MASK);
+
+    let lo = (lo_lo & MASK) | (middle << 64);
+    let hi = hi_hi + (lo_hi >> 64) + (hi_lo >> 64) + (middle >> 64);
+
+    (hi, lo)
+}
+
+// Computes (hi * 2^128 + lo) mod m, where hi < m. There is no 256 bit
+// division to fall back on. A modulus below 2^64 lets the
+// low word be shifted in 64 bits at a time with u128 division; larger moduli
+// shift it in one bit at a time.
+#[inline]
+pub(crate) const fn rem_wide_u128(hi: u128, lo: u128, modulus: u128) -> u128 {
+    if modulus <= u64::MAX as u128 {
+        let rem = hi % modulus;
+        let rem = ((rem << 64) | (lo >> 64)) % modulus;
+
+        return ((rem << 64) | (lo & (u64::MAX as u128))) % modulus;
+    }
+
+    let mut rem = hi;
+    let mut i = 128;
+
+    while i > 0 {
+        i -= 1;
+        let (doubled, carry) = rem.overflowing_add(rem);
+        rem = if carry || doubled >= modulus { doubled.wrapping_sub(modulus) } else { doubled };
+
+        if (lo >> i) & 1 == 1 {
+            let (sum, carry) = rem.overflowing_add(1);
+            rem = if carry || sum >= modulus { sum.wrapping_sub(modulus) } else { sum };
+        }
+    }
+
+    rem
+}
+
+// Computes x * y (mod m) for u128 values through the 256 bit product.
+#[inline]
+pub(crate) fn mul_mod_u128(x: u128, y: u128, modulus: u128) -> u128 {
+    let (hi, lo) = mul_wide_u128(x % modulus, y % modulus);
+
+    rem_wide_u128(hi, lo, modulus)
+}
+
+// Computes x * y (mod m) for i128 values, with the remainder floored like the
+// other signed implementations. The magnitudes are multiplied as u128, which
+// also covers operands and moduli equal to i128::MIN.
+#[inline]
+pub(crate) fn mul_mod_i128(x: i128, y: i128, modulus: i128) -> i128 {
+    let abs_modulus = modulus.unsigned_abs();
+    let magnitude = mul_mod_u128(x.unsigned_abs(), y.unsigned_abs(), abs_modulus);
+
+    // The residue of the product in [0, |m|).
+    let residue = if ((x < 0) != (y < 0)) && magnitude != 0 {
+        abs_modulus - magnitude
+    } else {
+        magnitude
+    };
+
+    if modulus > 0 || residue == 0 {
+        residue as i128
+    } else {
+        // Shift into (m, 0]. Here 0 < |m| - residue < 2^127.
+        -((abs_modulus - residue) as i128)
+    }
+}